@@ -3,7 +3,7 @@ use crate::error::Error;
 use bitflags::bitflags;
 use num_enum::TryFromPrimitive;
 use std::convert::TryFrom;
-use std::{fmt, mem};
+use std::{any, fmt, mem, slice};
 
 #[derive(Debug, Eq, PartialEq, TryFromPrimitive)]
 #[repr(u16)]
@@ -56,61 +56,121 @@ pub enum Chunk<'arsc> {
     StringPool(&'arsc [u8]),
     Spec(&'arsc [u8]),
     Type(&'arsc [u8]),
+    Library(&'arsc [u8]),
+    /// Padding; carries no data and can be skipped.
+    Null(&'arsc [u8]),
     Error(String),
 }
 
 impl<'arsc> Chunk<'arsc> {
+    pub fn header(&self) -> Option<&'arsc Header> {
+        match *self {
+            Chunk::Table(bytes)
+            | Chunk::Package(bytes)
+            | Chunk::StringPool(bytes)
+            | Chunk::Spec(bytes)
+            | Chunk::Type(bytes)
+            | Chunk::Library(bytes)
+            | Chunk::Null(bytes) => view(bytes, 0).ok(),
+            Chunk::Error(_) => None,
+        }
+    }
+
+    /// An iterator over the child chunks of a table or package chunk. Returns None for other
+    /// chunks, and for chunks too short to contain their own header.
     pub fn iter(&self) -> Option<ChunkIterator<'arsc>> {
         match self {
             Chunk::Table(bytes) | Chunk::Package(bytes) => {
-                #[allow(clippy::transmute_ptr_to_ptr)]
-                let header: &Header = unsafe { mem::transmute(&bytes[0]) };
-                let inner = &bytes[header.header_size.value() as usize..];
+                let header = self.header()?;
+                let inner = bytes.get(header.header_size.value() as usize..)?;
                 Some(ChunkIterator::new(inner))
             }
-            Chunk::StringPool(_) | Chunk::Spec(_) | Chunk::Type(_) | Chunk::Error(_) => None,
+            Chunk::StringPool(_)
+            | Chunk::Spec(_)
+            | Chunk::Type(_)
+            | Chunk::Library(_)
+            | Chunk::Null(_)
+            | Chunk::Error(_) => None,
         }
     }
 
     pub fn as_table(&self) -> Result<&'arsc Table, Error> {
         match *self {
-            #[allow(clippy::transmute_ptr_to_ptr)]
-            Chunk::Table(bytes) => Ok(unsafe { mem::transmute(&bytes[0]) }),
+            Chunk::Table(bytes) => view(bytes, 0),
             _ => Err(Error::UnexpectedChunk),
         }
     }
 
     pub fn as_package(&self) -> Result<&'arsc Package, Error> {
         match *self {
-            #[allow(clippy::transmute_ptr_to_ptr)]
-            Chunk::Package(bytes) => Ok(unsafe { mem::transmute(&bytes[0]) }),
+            Chunk::Package(bytes) => view(bytes, 0),
             _ => Err(Error::UnexpectedChunk),
         }
     }
 
     pub fn as_stringpool(&self) -> Result<&'arsc StringPool, Error> {
         match *self {
-            #[allow(clippy::transmute_ptr_to_ptr)]
-            Chunk::StringPool(bytes) => Ok(unsafe { mem::transmute(&bytes[0]) }),
+            Chunk::StringPool(bytes) => view(bytes, 0),
             _ => Err(Error::UnexpectedChunk),
         }
     }
 
     pub fn as_spec(&self) -> Result<&'arsc Spec, Error> {
         match *self {
-            #[allow(clippy::transmute_ptr_to_ptr)]
-            Chunk::Spec(bytes) => Ok(unsafe { mem::transmute(&bytes[0]) }),
+            Chunk::Spec(bytes) => view(bytes, 0),
             _ => Err(Error::UnexpectedChunk),
         }
     }
 
     pub fn as_type(&self) -> Result<&'arsc Type, Error> {
         match *self {
-            #[allow(clippy::transmute_ptr_to_ptr)]
-            Chunk::Type(bytes) => Ok(unsafe { mem::transmute(&bytes[0]) }),
+            Chunk::Type(bytes) => view(bytes, 0),
             _ => Err(Error::UnexpectedChunk),
         }
     }
+
+    pub fn as_library(&self) -> Result<&'arsc Library, Error> {
+        match *self {
+            Chunk::Library(bytes) => view(bytes, 0),
+            _ => Err(Error::UnexpectedChunk),
+        }
+    }
+}
+
+/// Marker for the `#[repr(C)]` structs in this module that can be laid directly on top of arsc
+/// data.
+///
+/// # Safety
+///
+/// Implementors must be built from byte arrays only, so any bit pattern is a valid value.
+pub(crate) unsafe trait View: Sized {}
+
+/// The `T` at `offset` in `bytes`, or `Error::CorruptData` if it does not fit.
+pub(crate) fn view<T: View>(bytes: &[u8], offset: usize) -> Result<&T, Error> {
+    view_slice(bytes, offset, 1).map(|slice| &slice[0])
+}
+
+/// The `count` consecutive `T`s at `offset` in `bytes`, or `Error::CorruptData` if they do not
+/// fit.
+pub(crate) fn view_slice<T: View>(
+    bytes: &[u8],
+    offset: usize,
+    count: usize,
+) -> Result<&[T], Error> {
+    let name = any::type_name::<T>().rsplit("::").next().unwrap();
+    let data = mem::size_of::<T>()
+        .checked_mul(count)
+        .and_then(|size| offset.checked_add(size))
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or_else(|| Error::CorruptData(format!("{} at {:#x} out of bounds", name, offset)))?;
+    if data.as_ptr().align_offset(mem::align_of::<T>()) != 0 {
+        return Err(Error::CorruptData(format!(
+            "{} at {:#x} misaligned",
+            name, offset
+        )));
+    }
+    // safe: T is View, and the data has been checked to be large enough and suitably aligned
+    Ok(unsafe { slice::from_raw_parts(data.as_ptr() as *const T, count) })
 }
 
 #[derive(Debug)]
@@ -121,9 +181,11 @@ pub struct Header {
     pub size: LittleEndianU32,
 }
 
+unsafe impl View for Header {}
+
 #[repr(C)]
 pub struct Configuration {
-    size: LittleEndianU32, // size of a Configuration, always 0x40
+    size: LittleEndianU32, // size of a Configuration: 0x40 today, smaller in legacy files
     pub imsi: LittleEndianU32,
     pub locale: LittleEndianU32,
     pub screen_type: LittleEndianU32,
@@ -132,6 +194,16 @@ pub struct Configuration {
     pub version: LittleEndianU32,
     pub screen_config: LittleEndianU32,
     pub screen_size_dp: LittleEndianU32,
+    pub locale_script: [u8; 4],
+    pub locale_variant: [u8; 8],
+}
+
+unsafe impl View for Configuration {}
+
+impl Configuration {
+    pub fn size(&self) -> u32 {
+        self.size.value()
+    }
 }
 
 impl fmt::Debug for Configuration {
@@ -167,6 +239,12 @@ impl fmt::Debug for Configuration {
                 self.screen_size_dp.value()
             ));
         }
+        if self.locale_script != [0; 4] {
+            v.push(format!("locale_script:{:?}", self.locale_script));
+        }
+        if self.locale_variant != [0; 8] {
+            v.push(format!("locale_variant:{:?}", self.locale_variant));
+        }
         if v.is_empty() {
             write!(f, "-")
         } else {
@@ -201,6 +279,16 @@ bitflags! {
     }
 }
 
+bitflags! {
+    pub struct EntryFlags: u16 {
+        // ResTable_entry::FLAG_*
+        const COMPLEX = 0x0001;
+        const PUBLIC = 0x0002;
+        const WEAK = 0x0004;
+        const COMPACT = 0x0008;
+    }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct Table {
@@ -208,6 +296,8 @@ pub struct Table {
     pub package_count: LittleEndianU32,
 }
 
+unsafe impl View for Table {}
+
 #[repr(C)]
 pub struct Package {
     pub header: Header,
@@ -219,6 +309,8 @@ pub struct Package {
     _unused_last_public_name: LittleEndianU32,
 }
 
+unsafe impl View for Package {}
+
 impl fmt::Debug for Package {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -240,13 +332,7 @@ pub struct StringPool {
     pub styles_offset: LittleEndianU32,
 }
 
-#[derive(Debug)]
-#[repr(C)]
-pub struct StringPoolSpan {
-    pub name: LittleEndianU32,
-    pub begin: LittleEndianU32,
-    pub end: LittleEndianU32,
-}
+unsafe impl View for StringPool {}
 
 #[derive(Debug)]
 #[repr(C)]
@@ -258,6 +344,8 @@ pub struct Spec {
     pub entry_count: LittleEndianU32,
 }
 
+unsafe impl View for Spec {}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct Type {
@@ -267,7 +355,36 @@ pub struct Type {
     _unused_padding16: LittleEndianU16,
     pub entry_count: LittleEndianU32,
     pub entries_offset: LittleEndianU32,
-    pub config: Configuration,
+    // followed by a Configuration, which can be shorter or longer than the struct (see its size)
+}
+
+unsafe impl View for Type {}
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct Library {
+    pub header: Header,
+    pub count: LittleEndianU32,
+}
+
+unsafe impl View for Library {}
+
+#[repr(C)]
+pub struct LibraryEntry {
+    pub package_id: LittleEndianU32,
+    pub package_name: [LittleEndianU16; 128],
+}
+
+unsafe impl View for LibraryEntry {}
+
+impl fmt::Debug for LibraryEntry {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "LibraryEntry {{ package_id: {:?}, ... }}",
+            self.package_id
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -278,6 +395,14 @@ pub struct Entry {
     pub key_index: LittleEndianU32,
 }
 
+unsafe impl View for Entry {}
+
+impl Entry {
+    pub fn flags(&self) -> EntryFlags {
+        EntryFlags::from_bits_truncate(self.flags.value())
+    }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct MapEntry {
@@ -286,6 +411,8 @@ pub struct MapEntry {
     pub count: LittleEndianU32,
 }
 
+unsafe impl View for MapEntry {}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct Value {
@@ -295,6 +422,8 @@ pub struct Value {
     pub data: LittleEndianU32,
 }
 
+unsafe impl View for Value {}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct KeyAndValue {
@@ -302,6 +431,8 @@ pub struct KeyAndValue {
     pub value: Value,
 }
 
+unsafe impl View for KeyAndValue {}
+
 #[derive(Debug)]
 pub struct ChunkIterator<'arsc> {
     data: &'arsc [u8],
@@ -329,17 +460,28 @@ impl<'arsc> Iterator for ChunkIterator<'arsc> {
 
         // read header
         let bytes_left = self.data.len() - self.offset;
-        if bytes_left < mem::size_of::<Header>() {
+        let header: &Header = match view(self.data, self.offset) {
+            Ok(header) => header,
+            Err(_) => {
+                self.invalidate();
+                return Some(Chunk::Error(format!(
+                    "{:#08x}: {} bytes left cannot contain header",
+                    self.offset, bytes_left
+                )));
+            }
+        };
+        let size = header.size.value() as usize;
+        let header_size = header.header_size.value() as usize;
+        if size < mem::size_of::<Header>() || header_size < mem::size_of::<Header>() {
             self.invalidate();
             return Some(Chunk::Error(format!(
-                "{:#08x}: {} bytes left cannot contain header",
-                self.offset, bytes_left
+                "{:#08x}: chunk size {} or header size {} less than {}",
+                self.offset,
+                size,
+                header_size,
+                mem::size_of::<Header>()
             )));
         }
-        #[allow(clippy::transmute_ptr_to_ptr)]
-        let header: &Header = unsafe { mem::transmute(&self.data[self.offset]) };
-        let size = header.size.value() as usize;
-        let header_size = header.header_size.value() as usize;
         if size < header_size {
             self.invalidate();
             return Some(Chunk::Error(format!(
@@ -367,14 +509,42 @@ impl<'arsc> Iterator for ChunkIterator<'arsc> {
         };
 
         // advance to next chunk and return
-        let bytes = &self.data[self.offset..self.offset + size];
+        let bytes = match self
+            .offset
+            .checked_add(size)
+            .and_then(|end| self.data.get(self.offset..end))
+        {
+            Some(bytes) => bytes,
+            None => {
+                self.invalidate();
+                return Some(Chunk::Error(format!(
+                    "{:#08x}: chunk of {} bytes out of bounds",
+                    self.offset, size
+                )));
+            }
+        };
         let chunk = match type_ {
             ChunkType::Table => Chunk::Table(bytes),
             ChunkType::Package => Chunk::Package(bytes),
             ChunkType::StringPool => Chunk::StringPool(bytes),
             ChunkType::Spec => Chunk::Spec(bytes),
             ChunkType::Type => Chunk::Type(bytes),
-            _ => todo!("{:?}", type_), // Null, Xml* not handled yet
+            ChunkType::Library => Chunk::Library(bytes),
+            ChunkType::Null => Chunk::Null(bytes),
+            ChunkType::Xml
+            | ChunkType::XmlStartNamespace
+            | ChunkType::XmlEndNamespace
+            | ChunkType::XmlStartElement
+            | ChunkType::XmlEndElement
+            | ChunkType::XmlCdata
+            | ChunkType::XmlResourceMap => {
+                let msg = format!(
+                    "{:#08x}: unexpected binary XML chunk {:?} in resource table",
+                    self.offset, type_
+                );
+                self.invalidate();
+                return Some(Chunk::Error(msg));
+            }
         };
         self.offset += size;
         Some(chunk)
@@ -383,7 +553,8 @@ impl<'arsc> Iterator for ChunkIterator<'arsc> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Chunk, ChunkIterator, ChunkType, Table};
+    use super::{Chunk, ChunkIterator, ChunkType, Header, Table};
+    use crate::error::Error;
     use std::convert::TryInto;
 
     const RESOURCE_ARSC: &[u8] = include_bytes!("../../tests/data/unpacked/resources.arsc");
@@ -393,6 +564,40 @@ mod tests {
         assert_eq!(0x0200u16.try_into(), Ok(ChunkType::Package));
     }
 
+    #[test]
+    fn header() {
+        let chunk = ChunkIterator::new(RESOURCE_ARSC).next().unwrap();
+        let header = chunk.header().unwrap();
+        assert_eq!(header.type_.value(), ChunkType::Table as u16);
+        assert_eq!(header.header_size.value(), 12);
+        assert_eq!(header.size.value() as usize, RESOURCE_ARSC.len());
+
+        let child = chunk.iter().unwrap().next().unwrap();
+        let header = child.header().unwrap();
+        assert_eq!(header.type_.value(), ChunkType::StringPool as u16);
+
+        assert!(Chunk::Error("foo".to_owned()).header().is_none());
+    }
+
+    #[test]
+    fn view() {
+        let bytes = &RESOURCE_ARSC[..12];
+        let header: &Header = super::view(bytes, 0).unwrap();
+        assert_eq!(header.type_.value(), ChunkType::Table as u16);
+        assert!(super::view::<Header>(bytes, 4).is_ok());
+        match super::view::<Header>(bytes, 5) {
+            Err(Error::CorruptData(msg)) => assert_eq!(msg, "Header at 0x5 out of bounds"),
+            _ => panic!("expected Error::CorruptData"),
+        }
+        assert!(super::view::<Table>(&bytes[..11], 0).is_err());
+        assert!(super::view_slice::<Header>(bytes, 0, usize::MAX).is_err());
+        assert!(super::view_slice::<Header>(bytes, usize::MAX, 1).is_err());
+        assert_eq!(super::view_slice::<Header>(bytes, 12, 0).unwrap().len(), 0);
+
+        // chunks too short for their struct are rejected instead of read past the end
+        assert!(Chunk::Table(&bytes[..8]).as_table().is_err());
+    }
+
     #[test]
     fn iter_valid_data() {
         fn iterate(iter: ChunkIterator, depth: usize, out: &mut Vec<String>) {
@@ -434,6 +639,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn iter_children_of_corrupt_chunk() {
+        // a table chunk with a header size larger than the chunk
+        let bytes = [
+            0x02, 0x00, 0x20, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert!(Chunk::Table(&bytes).iter().is_none());
+        assert!(Chunk::Package(&bytes[..4]).iter().is_none());
+
+        let mut bytes = bytes;
+        bytes[2] = 0x0c;
+        assert!(Chunk::Table(&bytes).iter().unwrap().next().is_none());
+    }
+
+    #[test]
+    fn iter_chunk_size_overflow() {
+        // a table chunk claiming to be 0xffffffff bytes long
+        let bytes = [
+            0x02, 0x00, 0x0c, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut iter = ChunkIterator::new(&bytes);
+        match iter.next() {
+            Some(Chunk::Error(_)) => {}
+            x => panic!("expected error, got {:?}", x),
+        }
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn iter_null_and_xml_chunks() {
+        let bytes = [
+            0x00, 0x00, 0x08, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // null
+            0x03, 0x00, 0x08, 0x00, 0x08, 0x00, 0x00, 0x00, // xml
+        ];
+        let mut iter = ChunkIterator::new(&bytes);
+        match iter.next() {
+            Some(Chunk::Null(bytes)) => assert_eq!(bytes.len(), 12),
+            x => panic!("expected null chunk, got {:?}", x),
+        }
+        match iter.next() {
+            Some(Chunk::Error(msg)) => {
+                assert_eq!(
+                    msg,
+                    "0x00000c: unexpected binary XML chunk Xml in resource table"
+                )
+            }
+            x => panic!("expected error, got {:?}", x),
+        }
+        assert!(iter.next().is_none());
+
+        // all zero bytes must not be mistaken for an endless run of empty null chunks
+        let mut iter = ChunkIterator::new(&[0; 16]);
+        match iter.next() {
+            Some(Chunk::Error(_)) => {}
+            x => panic!("expected error, got {:?}", x),
+        }
+        assert!(iter.next().is_none());
+    }
+
     #[test]
     fn try_from_chunk_to_table() {
         let mut iter = ChunkIterator::new(RESOURCE_ARSC);
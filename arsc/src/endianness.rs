@@ -1,3 +1,9 @@
+// The multi-byte types are stored as byte arrays, not as u16/u32, so that all types (and all
+// #[repr(C)] structs built from them) have an alignment of 1 and can be read from any offset in
+// an arbitrary byte buffer.
+
+use crate::error::Error;
+
 #[derive(Debug, Eq, PartialEq)]
 #[repr(C)]
 pub struct LittleEndianU8 {
@@ -13,39 +19,50 @@ impl LittleEndianU8 {
 #[derive(Debug, Eq, PartialEq)]
 #[repr(C)]
 pub struct LittleEndianU16 {
-    value: u16,
+    value: [u8; 2],
 }
 
 impl LittleEndianU16 {
     pub fn value(&self) -> u16 {
-        u16::from_le(self.value)
+        u16::from_le_bytes(self.value)
+    }
+
+    pub fn read_at(bytes: &[u8], offset: usize) -> Option<u16> {
+        let b = bytes.get(offset..offset.checked_add(2)?)?;
+        Some(u16::from_le_bytes([b[0], b[1]]))
     }
 
-    pub fn decode_string(array: &[LittleEndianU16; 128]) -> String {
-        let mut v = Vec::with_capacity(array.len());
-        array
+    /// Decode a NUL-terminated UTF-16 string; anything after the terminator is ignored.
+    pub fn decode_string(array: &[LittleEndianU16; 128]) -> Result<String, Error> {
+        let v = array
             .iter()
-            .filter(|ch| ch.value() != 0x00)
-            .for_each(|ch| v.push(ch.value()));
-        String::from_utf16_lossy(&v)
+            .map(|ch| ch.value())
+            .take_while(|ch| *ch != 0x00)
+            .collect::<Vec<_>>();
+        String::from_utf16(&v).map_err(|_| Error::CorruptData(format!("bad UTF-16 {:04x?}", v)))
     }
 }
 
 #[derive(Debug, Eq, PartialEq)]
 #[repr(C)]
 pub struct LittleEndianU32 {
-    value: u32,
+    value: [u8; 4],
 }
 
 impl LittleEndianU32 {
     pub fn value(&self) -> u32 {
-        u32::from_le(self.value)
+        u32::from_le_bytes(self.value)
+    }
+
+    pub fn read_at(bytes: &[u8], offset: usize) -> Option<u32> {
+        let b = bytes.get(offset..offset.checked_add(4)?)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{LittleEndianU16, LittleEndianU8};
+    use super::{LittleEndianU16, LittleEndianU32, LittleEndianU8};
 
     #[test]
     fn little_endian_to_native_endian() {
@@ -55,6 +72,15 @@ mod tests {
         assert_eq!(int.value(), 32u8);
     }
 
+    #[test]
+    fn read_at() {
+        let bytes = [0x00, 0x01, 0x02, 0x03, 0x04];
+        assert_eq!(LittleEndianU16::read_at(&bytes, 1), Some(0x0201));
+        assert_eq!(LittleEndianU32::read_at(&bytes, 1), Some(0x0403_0201));
+        assert_eq!(LittleEndianU32::read_at(&bytes, 2), None);
+        assert_eq!(LittleEndianU32::read_at(&bytes, usize::MAX), None);
+    }
+
     #[test]
     fn decode_package_name() {
         let bytes: [u8; 256] = [
@@ -80,6 +106,25 @@ mod tests {
         ];
         let ptr = bytes.as_ptr() as *const [LittleEndianU16; 128];
         let utf16_chars = unsafe { &*ptr };
-        assert_eq!("test.app", LittleEndianU16::decode_string(utf16_chars));
+        assert_eq!(
+            "test.app",
+            LittleEndianU16::decode_string(utf16_chars).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_string_stops_at_nul() {
+        let decode = |s: &[u16]| {
+            let mut bytes = vec![0u8; 256];
+            for (i, ch) in s.iter().enumerate() {
+                bytes[2 * i..2 * i + 2].copy_from_slice(&ch.to_le_bytes());
+            }
+            let ptr = bytes.as_ptr() as *const [LittleEndianU16; 128];
+            LittleEndianU16::decode_string(unsafe { &*ptr })
+        };
+        let name = decode(&[0x61, 0x2e, 0x62, 0x00, 0x78, 0x79, 0x00, 0x7a]).unwrap();
+        assert_eq!(name, "a.b");
+        assert_eq!(decode(&[]).unwrap(), "");
+        assert!(decode(&[0x61, 0xd800, 0x62]).is_err()); // unpaired surrogate
     }
 }
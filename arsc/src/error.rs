@@ -1,18 +1,34 @@
 use std::convert::From;
-use std::{fmt, io};
+use std::{error, fmt, io};
 
 #[derive(Debug)]
 pub enum Error {
     BadIndex,
     CorruptData(String),
+    InvalidInput(String),
     IoError(io::Error),
     UnexpectedChunk,
+    Unsupported(String),
 }
 
 impl fmt::Display for Error {
-    fn fmt(&self, _f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            _ => todo!(),
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::BadIndex => write!(f, "index out of range"),
+            Error::CorruptData(msg) => write!(f, "corrupt data: {}", msg),
+            Error::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
+            Error::IoError(e) => write!(f, "I/O error: {}", e),
+            Error::UnexpectedChunk => write!(f, "unexpected chunk"),
+            Error::Unsupported(what) => write!(f, "unsupported: {}", what),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::IoError(e) => Some(e),
+            _ => None,
         }
     }
 }
@@ -22,3 +38,25 @@ impl From<io::Error> for Error {
         Error::IoError(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use std::error::Error as _;
+    use std::io;
+
+    #[test]
+    fn display() {
+        assert_eq!(Error::BadIndex.to_string(), "index out of range");
+        assert_eq!(
+            Error::CorruptData("foo".to_owned()).to_string(),
+            "corrupt data: foo"
+        );
+        assert_eq!(Error::UnexpectedChunk.to_string(), "unexpected chunk");
+
+        let e = Error::from(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+        assert_eq!(e.to_string(), "I/O error: no such file");
+        assert!(e.source().is_some());
+        assert!(Error::BadIndex.source().is_none());
+    }
+}
@@ -1,10 +1,24 @@
 mod chunks;
 mod endianness;
 mod error;
+mod matching;
+mod owned;
+mod qualifier;
+mod resolver;
 mod resources;
 mod stringpool;
 mod table;
+#[cfg(test)]
+mod test_utils;
 
+pub use chunks::{ConfigurationFlags, EntryFlags};
 pub use error::Error;
-pub use resources::ResourceId;
-pub use table::LoadedTable as Table;
+pub use owned::OwnedTable;
+pub use resolver::ResourceResolver;
+pub use resources::{
+    ComplexValue, DimensionUnit, FractionUnit, ResourceConfiguration, ResourceId, ResourceValue,
+};
+pub use stringpool::{
+    LoadedStringPool as StringPool, LoadedStringPoolSpan as StringPoolSpan, StyledString,
+};
+pub use table::{LoadedTable as Table, PackageRef, ParseOptions, PoolKind, ResourceEntry};
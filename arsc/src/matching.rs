@@ -0,0 +1,359 @@
+//! Selection of the configuration that best matches a device configuration, following
+//! ResTable_config::match and ResTable_config::isBetterThan. Axes this crate does not decode
+//! (round screens, color modes, grammatical gender) are ignored.
+
+use crate::resources::ResourceConfiguration;
+
+const DENSITY_MEDIUM: u32 = 160;
+const DENSITY_ANY: u32 = 0xfffe;
+const SCREENSIZE_NORMAL: u32 = 2;
+const KEYSHIDDEN_NO: u32 = 1;
+const KEYSHIDDEN_SOFT: u32 = 3;
+
+// A config's fields, unpacked into the sub-fields aapt compares.
+struct Fields {
+    mcc: u32,
+    mnc: u32,
+    language: u32,
+    region: u32,
+    orientation: u32,
+    touchscreen: u32,
+    density: u32,
+    keyboard: u32,
+    navigation: u32,
+    keys_hidden: u32,
+    nav_hidden: u32,
+    screen_width: u32,
+    screen_height: u32,
+    sdk_version: u32,
+    layout_dir: u32,
+    screen_layout_size: u32,
+    screen_long: u32,
+    ui_mode_type: u32,
+    ui_mode_night: u32,
+    smallest_screen_width_dp: u32,
+    screen_width_dp: u32,
+    screen_height_dp: u32,
+}
+
+impl Fields {
+    fn new(config: &ResourceConfiguration) -> Fields {
+        Fields {
+            mcc: config.imsi & 0xffff,
+            mnc: config.imsi >> 16,
+            language: config.locale & 0xffff,
+            region: config.locale >> 16,
+            orientation: config.screen_type & 0xff,
+            touchscreen: (config.screen_type >> 8) & 0xff,
+            density: config.screen_type >> 16,
+            keyboard: config.input & 0xff,
+            navigation: (config.input >> 8) & 0xff,
+            keys_hidden: (config.input >> 16) & 0x03,
+            nav_hidden: (config.input >> 18) & 0x03,
+            screen_width: config.screen_size & 0xffff,
+            screen_height: config.screen_size >> 16,
+            sdk_version: config.version & 0xffff,
+            layout_dir: (config.screen_config >> 6) & 0x03,
+            screen_layout_size: config.screen_config & 0x0f,
+            screen_long: (config.screen_config >> 4) & 0x03,
+            ui_mode_type: (config.screen_config >> 8) & 0x0f,
+            ui_mode_night: (config.screen_config >> 12) & 0x03,
+            smallest_screen_width_dp: config.screen_config >> 16,
+            screen_width_dp: config.screen_size_dp & 0xffff,
+            screen_height_dp: config.screen_size_dp >> 16,
+        }
+    }
+}
+
+// A resource value set on an axis compared by equality matches only the same device value.
+fn same_or_any(value: u32, requested: u32) -> bool {
+    value == 0 || value == requested
+}
+
+// A resource value set on an axis compared by size matches any device value at least as large.
+fn at_most(value: u32, requested: u32) -> bool {
+    value == 0 || value <= requested
+}
+
+impl ResourceConfiguration {
+    /// Whether a value in this configuration can be used on a device with the `requested`
+    /// configuration. Every axis set here must be compatible with the request; the density is
+    /// never a reason to reject a configuration. The default configuration matches everything.
+    pub fn matches(&self, requested: &ResourceConfiguration) -> bool {
+        let (me, req) = (Fields::new(self), Fields::new(requested));
+        let keys_hidden = me.keys_hidden == 0
+            || me.keys_hidden == req.keys_hidden
+            || (me.keys_hidden == KEYSHIDDEN_NO && req.keys_hidden == KEYSHIDDEN_SOFT);
+        same_or_any(me.mcc, req.mcc)
+            && same_or_any(me.mnc, req.mnc)
+            && same_or_any(me.language, req.language)
+            && same_or_any(me.region, req.region)
+            && (self.locale_script == [0; 4] || self.locale_script == requested.locale_script)
+            && (self.locale_variant == [0; 8] || self.locale_variant == requested.locale_variant)
+            && same_or_any(me.layout_dir, req.layout_dir)
+            && at_most(me.smallest_screen_width_dp, req.smallest_screen_width_dp)
+            && at_most(me.screen_width_dp, req.screen_width_dp)
+            && at_most(me.screen_height_dp, req.screen_height_dp)
+            && at_most(me.screen_layout_size, req.screen_layout_size)
+            && same_or_any(me.screen_long, req.screen_long)
+            && same_or_any(me.orientation, req.orientation)
+            && same_or_any(me.ui_mode_type, req.ui_mode_type)
+            && same_or_any(me.ui_mode_night, req.ui_mode_night)
+            && same_or_any(me.touchscreen, req.touchscreen)
+            && keys_hidden
+            && same_or_any(me.nav_hidden, req.nav_hidden)
+            && same_or_any(me.keyboard, req.keyboard)
+            && same_or_any(me.navigation, req.navigation)
+            && at_most(me.screen_width, req.screen_width)
+            && at_most(me.screen_height, req.screen_height)
+            && at_most(me.sdk_version, req.sdk_version)
+    }
+
+    /// Whether this configuration is a better match than `other` for a device with the
+    /// `requested` configuration. Both configurations are expected to match the request. The
+    /// axes are compared in aapt's order of precedence; the first axis that decides wins.
+    pub fn is_better_than(
+        &self,
+        other: &ResourceConfiguration,
+        requested: &ResourceConfiguration,
+    ) -> bool {
+        let (me, o, req) = (
+            Fields::new(self),
+            Fields::new(other),
+            Fields::new(requested),
+        );
+
+        if me.mcc != o.mcc && req.mcc != 0 {
+            return me.mcc != 0;
+        }
+        if me.mnc != o.mnc && req.mnc != 0 {
+            return me.mnc != 0;
+        }
+        if let Some(better) = self.is_locale_better_than(other, requested) {
+            return better;
+        }
+        if me.layout_dir != o.layout_dir && req.layout_dir != 0 {
+            return me.layout_dir > o.layout_dir;
+        }
+        if me.smallest_screen_width_dp != o.smallest_screen_width_dp {
+            return me.smallest_screen_width_dp > o.smallest_screen_width_dp;
+        }
+        let (my_delta, other_delta) = deltas(
+            (me.screen_width_dp, me.screen_height_dp),
+            (o.screen_width_dp, o.screen_height_dp),
+            (req.screen_width_dp, req.screen_height_dp),
+        );
+        if my_delta != other_delta {
+            return my_delta < other_delta;
+        }
+        if me.screen_layout_size != o.screen_layout_size && req.screen_layout_size != 0 {
+            // a normal screen is assumed for configurations without a size
+            let fixed = |size| match size {
+                0 if req.screen_layout_size >= SCREENSIZE_NORMAL => SCREENSIZE_NORMAL,
+                size => size,
+            };
+            if fixed(me.screen_layout_size) == fixed(o.screen_layout_size) {
+                return me.screen_layout_size != 0;
+            }
+            return fixed(me.screen_layout_size) > fixed(o.screen_layout_size);
+        }
+        if me.screen_long != o.screen_long && req.screen_long != 0 {
+            return me.screen_long != 0;
+        }
+        if me.orientation != o.orientation && req.orientation != 0 {
+            return me.orientation != 0;
+        }
+        if me.ui_mode_type != o.ui_mode_type && req.ui_mode_type != 0 {
+            return me.ui_mode_type != 0;
+        }
+        if me.ui_mode_night != o.ui_mode_night && req.ui_mode_night != 0 {
+            return me.ui_mode_night != 0;
+        }
+        if me.density != o.density {
+            return is_density_better(me.density, o.density, req.density);
+        }
+        if me.touchscreen != o.touchscreen && req.touchscreen != 0 {
+            return me.touchscreen != 0;
+        }
+        if me.keys_hidden != o.keys_hidden && req.keys_hidden != 0 {
+            if me.keys_hidden == 0 || o.keys_hidden == 0 {
+                return me.keys_hidden != 0;
+            }
+            // keysexposed counts as a match for keyssoft, but an exact match wins
+            if me.keys_hidden == req.keys_hidden {
+                return true;
+            }
+            if o.keys_hidden == req.keys_hidden {
+                return false;
+            }
+        }
+        if me.nav_hidden != o.nav_hidden && req.nav_hidden != 0 {
+            return me.nav_hidden != 0;
+        }
+        if me.keyboard != o.keyboard && req.keyboard != 0 {
+            return me.keyboard != 0;
+        }
+        if me.navigation != o.navigation && req.navigation != 0 {
+            return me.navigation != 0;
+        }
+        let (my_delta, other_delta) = deltas(
+            (me.screen_width, me.screen_height),
+            (o.screen_width, o.screen_height),
+            (req.screen_width, req.screen_height),
+        );
+        if my_delta != other_delta {
+            return my_delta < other_delta;
+        }
+        if me.sdk_version != o.sdk_version && req.sdk_version != 0 {
+            return me.sdk_version > o.sdk_version;
+        }
+        false
+    }
+
+    // Some(true) or Some(false) if the locale decides which configuration is better, None if
+    // the next axis has to be looked at.
+    fn is_locale_better_than(
+        &self,
+        other: &ResourceConfiguration,
+        requested: &ResourceConfiguration,
+    ) -> Option<bool> {
+        if requested.locale & 0xffff == 0 {
+            return None;
+        }
+        let (me, o) = (Fields::new(self), Fields::new(other));
+        if me.language != o.language {
+            return Some(me.language != 0);
+        }
+        if self.locale_script != other.locale_script {
+            return Some(self.locale_script != [0; 4]);
+        }
+        if me.region != o.region {
+            return Some(me.region != 0);
+        }
+        if self.locale_variant != other.locale_variant {
+            return Some(self.locale_variant != [0; 8]);
+        }
+        None
+    }
+}
+
+// How far each configuration's width and height fall short of the requested ones, summed over
+// the dimensions the request specifies.
+fn deltas(mine: (u32, u32), other: (u32, u32), requested: (u32, u32)) -> (i64, i64) {
+    let mut my_delta = 0;
+    let mut other_delta = 0;
+    if requested.0 != 0 {
+        my_delta += requested.0 as i64 - mine.0 as i64;
+        other_delta += requested.0 as i64 - other.0 as i64;
+    }
+    if requested.1 != 0 {
+        my_delta += requested.1 as i64 - mine.1 as i64;
+        other_delta += requested.1 as i64 - other.1 as i64;
+    }
+    (my_delta, other_delta)
+}
+
+// Prefer the density closest to the requested one; scaling down is preferred over scaling up,
+// and anydpi over any scaling at all.
+fn is_density_better(mine: u32, other: u32, requested: u32) -> bool {
+    let mine = if mine == 0 { DENSITY_MEDIUM } else { mine };
+    let other = if other == 0 { DENSITY_MEDIUM } else { other };
+    if mine == DENSITY_ANY {
+        return true;
+    }
+    if other == DENSITY_ANY {
+        return false;
+    }
+    let requested = match requested {
+        0 | DENSITY_ANY => DENSITY_MEDIUM,
+        requested => requested,
+    };
+    let (high, low, mine_is_higher) = if mine >= other {
+        (mine, other, true)
+    } else {
+        (other, mine, false)
+    };
+    if requested >= high {
+        return mine_is_higher;
+    }
+    if low >= requested {
+        return !mine_is_higher;
+    }
+    // the requested density lies between the two
+    if (2 * low as i64 - requested as i64) * high as i64 > requested as i64 * requested as i64 {
+        !mine_is_higher
+    } else {
+        mine_is_higher
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::ResourceConfiguration;
+
+    fn config(s: &str) -> ResourceConfiguration {
+        ResourceConfiguration::parse_qualifier(s).unwrap()
+    }
+
+    // the qualifier of the best of the given configurations for the request, as aapt would pick
+    fn best<'a>(candidates: &[&'a str], requested: &str) -> Option<&'a str> {
+        let requested = config(requested);
+        let mut best: Option<&str> = None;
+        for candidate in candidates {
+            if !config(candidate).matches(&requested) {
+                continue;
+            }
+            let better = match best {
+                Some(b) => config(candidate).is_better_than(&config(b), &requested),
+                None => true,
+            };
+            if better {
+                best = Some(candidate);
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn matches() {
+        assert!(config("").matches(&config("en-rUS-xxhdpi-v30")));
+        assert!(config("en").matches(&config("en-rUS")));
+        assert!(!config("en-rGB").matches(&config("en-rUS")));
+        assert!(!config("fr").matches(&config("en-rUS")));
+        assert!(config("xxxhdpi").matches(&config("mdpi")));
+        assert!(config("v21").matches(&config("v30")));
+        assert!(!config("v31").matches(&config("v30")));
+        assert!(config("sw600dp").matches(&config("sw720dp")));
+        assert!(!config("sw600dp").matches(&config("sw320dp")));
+        assert!(!config("land").matches(&config("port")));
+        assert!(config("keysexposed").matches(&config("keyssoft")));
+    }
+
+    #[test]
+    fn is_better_than() {
+        let candidates = ["", "en", "en-rUS", "fr", "v21"];
+        assert_eq!(best(&candidates, "en-rUS-v30"), Some("en-rUS"));
+        assert_eq!(best(&candidates, "en-rGB-v30"), Some("en"));
+        assert_eq!(best(&candidates, "de-v30"), Some("v21"));
+        assert_eq!(best(&candidates, "de-v19"), Some(""));
+
+        let densities = ["", "mdpi", "hdpi", "xxhdpi"];
+        assert_eq!(best(&densities, "xxhdpi"), Some("xxhdpi"));
+        assert_eq!(best(&densities, "xhdpi"), Some("xxhdpi")); // scale down rather than up
+        assert_eq!(best(&densities, "xxxhdpi"), Some("xxhdpi"));
+        assert_eq!(best(&["hdpi", "xxhdpi"], "ldpi"), Some("hdpi"));
+        assert_eq!(best(&["hdpi", "anydpi"], "xxhdpi"), Some("anydpi"));
+
+        let widths = ["", "sw320dp", "sw600dp"];
+        assert_eq!(best(&widths, "sw720dp"), Some("sw600dp"));
+        assert_eq!(best(&widths, "sw480dp"), Some("sw320dp"));
+
+        let versions = ["", "v21", "v26"];
+        assert_eq!(best(&versions, "v28"), Some("v26"));
+        assert_eq!(best(&versions, "v23"), Some("v21"));
+
+        // the locale takes precedence over the density
+        assert_eq!(best(&["en", "xxhdpi"], "en-xxhdpi"), Some("en"));
+        assert_eq!(best(&["fr"], "en"), None);
+    }
+}
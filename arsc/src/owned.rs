@@ -0,0 +1,238 @@
+use crate::error::Error;
+use crate::resources::{ResourceConfiguration, ResourceId, ResourceValue};
+use crate::table::{LoadedTable, ParseOptions};
+use std::collections::HashMap;
+
+/// A resource table that owns its bytes, for when the borrowing `LoadedTable` is impractical,
+/// e.g. to return a parsed table from a function or store it in a struct.
+///
+/// Every resource's names and values are decoded once, when the table is parsed, and the
+/// lookups below are answered from those without touching the bytes again. Only `table`
+/// goes back to the bytes, and it parses them anew on every call.
+pub struct OwnedTable {
+    bytes: Vec<u8>,
+    options: ParseOptions,
+    warnings: Vec<String>,
+    // in the same order as LoadedTable::resid_iter
+    entries: Vec<OwnedEntry>,
+    index_by_resid: HashMap<ResourceId, usize>,
+    resid_by_name: HashMap<(String, String, String), ResourceId>,
+}
+
+struct OwnedEntry {
+    id: ResourceId,
+    name: (String, String, String),
+    // None for a value that could not be decoded
+    values: Vec<(ResourceConfiguration, Option<ResourceValue>)>,
+}
+
+impl OwnedTable {
+    pub fn parse(bytes: Vec<u8>) -> Result<OwnedTable, Error> {
+        OwnedTable::parse_with_options(bytes, ParseOptions::default())
+    }
+
+    pub fn parse_with_options(bytes: Vec<u8>, options: ParseOptions) -> Result<OwnedTable, Error> {
+        let table = LoadedTable::parse_with_options(&bytes, options)?;
+        let warnings = table.warnings().to_vec();
+        let entries = table
+            .entries()
+            .map(|entry| OwnedEntry {
+                id: entry.id(),
+                name: (
+                    entry.package_name().to_owned(),
+                    entry.type_name().to_owned(),
+                    entry.name().to_owned(),
+                ),
+                values: entry.each_value(),
+            })
+            .collect::<Vec<_>>();
+        drop(table);
+
+        let mut index_by_resid = HashMap::with_capacity(entries.len());
+        let mut resid_by_name = HashMap::with_capacity(entries.len());
+        for (i, entry) in entries.iter().enumerate() {
+            index_by_resid.insert(entry.id, i);
+            // like LoadedTable::resid_for_name, the first package with a given name wins
+            resid_by_name.entry(entry.name.clone()).or_insert(entry.id);
+        }
+        Ok(OwnedTable {
+            bytes,
+            options,
+            warnings,
+            entries,
+            index_by_resid,
+            resid_by_name,
+        })
+    }
+
+    /// A `LoadedTable` borrowing this table's bytes, for the queries this type does not provide.
+    ///
+    /// This parses the whole table again on every call: call it once and keep the result around
+    /// rather than calling it in a loop.
+    pub fn table(&self) -> LoadedTable<'_> {
+        LoadedTable::parse_with_options(&self.bytes, self.options)
+            .expect("bytes that parsed before parse again")
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Problems found and worked around while parsing in non-strict mode.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn resid_iter(&self) -> impl Iterator<Item = ResourceId> + '_ {
+        self.entries.iter().map(|entry| entry.id)
+    }
+
+    pub fn resid_for_name(
+        &self,
+        package_name: &str,
+        type_name: &str,
+        entry_name: &str,
+    ) -> Option<ResourceId> {
+        let key = (
+            package_name.to_owned(),
+            type_name.to_owned(),
+            entry_name.to_owned(),
+        );
+        self.resid_by_name.get(&key).copied()
+    }
+
+    pub fn name_for_resid(&self, resid: &ResourceId) -> Option<(String, String, String)> {
+        Some(self.entry(resid)?.name.clone())
+    }
+
+    /// Same as `LoadedTable::value_for_resid`.
+    pub fn value_for_resid(
+        &self,
+        resid: &ResourceId,
+    ) -> Option<Vec<(ResourceConfiguration, ResourceValue)>> {
+        self.entry(resid)?
+            .values
+            .iter()
+            .map(|(config, value)| Some((config.clone(), value.clone()?)))
+            .collect()
+    }
+
+    /// Same as `LoadedTable::value_for_resid_config`.
+    pub fn value_for_resid_config(
+        &self,
+        resid: &ResourceId,
+        config: &ResourceConfiguration,
+    ) -> Option<ResourceValue> {
+        let mut best: Option<&(ResourceConfiguration, Option<ResourceValue>)> = None;
+        for config_and_value in self.entry(resid)?.values.iter() {
+            if !config_and_value.0.matches(config) {
+                continue;
+            }
+            let better = match best {
+                Some(b) => config_and_value.0.is_better_than(&b.0, config),
+                None => true,
+            };
+            if better {
+                best = Some(config_and_value);
+            }
+        }
+        best?.1.clone()
+    }
+
+    /// Same as `LoadedTable::configurations_for_resid`.
+    pub fn configurations_for_resid(
+        &self,
+        resid: &ResourceId,
+    ) -> Option<Vec<ResourceConfiguration>> {
+        let entry = self.entry(resid)?;
+        Some(
+            entry
+                .values
+                .iter()
+                .map(|(config, _)| config.clone())
+                .collect(),
+        )
+    }
+
+    fn entry(&self, resid: &ResourceId) -> Option<&OwnedEntry> {
+        Some(&self.entries[*self.index_by_resid.get(resid)?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OwnedTable;
+    use crate::resources::{ResourceConfiguration, ResourceId};
+    use crate::table::{LoadedTable, ParseOptions};
+
+    const RESOURCE_ARSC: &[u8] = include_bytes!("../../tests/data/unpacked/resources.arsc");
+
+    // the point of an owned table: it can outlive the scope that read the bytes
+    fn load() -> OwnedTable {
+        LoadedTable::parse_owned(RESOURCE_ARSC.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn lookups() {
+        let owned = load();
+        let loaded = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert_eq!(
+            owned.resid_iter().collect::<Vec<_>>(),
+            loaded.resid_iter().collect::<Vec<_>>()
+        );
+        let configs = ["", "sv", "sv-rSE-xxhdpi", "en-rXA", "de-v30"]
+            .iter()
+            .map(|q| ResourceConfiguration::parse_qualifier(q).unwrap())
+            .collect::<Vec<_>>();
+        for resid in loaded.resid_iter() {
+            let name = owned.name_for_resid(&resid).unwrap();
+            assert_eq!(Some(&name), loaded.name_for_resid(&resid).as_ref());
+            assert_eq!(owned.resid_for_name(&name.0, &name.1, &name.2), Some(resid));
+            assert!(owned.value_for_resid(&resid).is_some());
+            assert_eq!(
+                owned.value_for_resid(&resid),
+                loaded.value_for_resid(&resid)
+            );
+            assert_eq!(
+                owned.configurations_for_resid(&resid),
+                loaded.configurations_for_resid(&resid)
+            );
+            for config in &configs {
+                assert_eq!(
+                    owned.value_for_resid_config(&resid, config),
+                    loaded.value_for_resid_config(&resid, config)
+                );
+            }
+        }
+
+        let missing = ResourceId::from_u32(0x7f02_ffff);
+        assert!(owned.name_for_resid(&missing).is_none());
+        assert!(owned.value_for_resid(&missing).is_none());
+        assert!(owned.configurations_for_resid(&missing).is_none());
+        assert!(owned
+            .resid_for_name("test.app", "string", "missing")
+            .is_none());
+        assert!(owned.warnings().is_empty());
+
+        assert_eq!(owned.table().package_names(), loaded.package_names());
+        assert_eq!(owned.into_bytes(), RESOURCE_ARSC);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(OwnedTable::parse(vec![0; 16]).is_err());
+
+        let mut bytes = RESOURCE_ARSC.to_vec();
+        bytes.push(1);
+        assert!(OwnedTable::parse(bytes.clone()).is_err());
+        let lenient = ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        };
+        assert!(OwnedTable::parse_with_options(bytes, lenient).is_err());
+    }
+}
@@ -0,0 +1,590 @@
+use crate::error::Error;
+use crate::resources::ResourceConfiguration;
+
+#[derive(Clone, Copy)]
+enum Field {
+    Imsi,
+    Locale,
+    ScreenType,
+    Input,
+    ScreenSize,
+    Version,
+    ScreenConfig,
+    ScreenSizeDp,
+}
+
+enum Format {
+    Named(&'static [(u32, &'static str)]),
+    Numeric(&'static str, &'static str), // prefix, suffix
+    Mnc,
+    Language,
+    Region,
+    Density,
+    ScreenSize,
+}
+
+// One part of a qualifier string, e.g. "hdpi" for the density. The value of the axis is stored
+// as (field & mask) >> shift; a value of 0 means "any" and is not rendered.
+struct Axis {
+    field: Field,
+    mask: u32,
+    shift: u32,
+    format: Format,
+}
+
+const MNC_ZERO: u32 = 0xffff;
+
+const LAYOUT_DIRS: &[(u32, &str)] = &[(1, "ldltr"), (2, "ldrtl")];
+const SCREEN_SIZES: &[(u32, &str)] = &[(1, "small"), (2, "normal"), (3, "large"), (4, "xlarge")];
+const SCREEN_LONG: &[(u32, &str)] = &[(1, "notlong"), (2, "long")];
+const ORIENTATIONS: &[(u32, &str)] = &[(1, "port"), (2, "land"), (3, "square")];
+const UI_MODE_TYPES: &[(u32, &str)] = &[
+    (2, "desk"),
+    (3, "car"),
+    (4, "television"),
+    (5, "appliance"),
+    (6, "watch"),
+    (7, "vrheadset"),
+];
+const UI_MODE_NIGHT: &[(u32, &str)] = &[(1, "notnight"), (2, "night")];
+const DENSITIES: &[(u32, &str)] = &[
+    (120, "ldpi"),
+    (160, "mdpi"),
+    (213, "tvdpi"),
+    (240, "hdpi"),
+    (320, "xhdpi"),
+    (480, "xxhdpi"),
+    (640, "xxxhdpi"),
+    (0xfffe, "anydpi"),
+    (0xffff, "nodpi"),
+];
+const TOUCHSCREENS: &[(u32, &str)] = &[(1, "notouch"), (2, "stylus"), (3, "finger")];
+const KEYS_HIDDEN: &[(u32, &str)] = &[(1, "keysexposed"), (2, "keyshidden"), (3, "keyssoft")];
+const KEYBOARDS: &[(u32, &str)] = &[(1, "nokeys"), (2, "qwerty"), (3, "12key")];
+const NAV_HIDDEN: &[(u32, &str)] = &[(1, "navexposed"), (2, "navhidden")];
+const NAVIGATIONS: &[(u32, &str)] = &[(1, "nonav"), (2, "dpad"), (3, "trackball"), (4, "wheel")];
+
+// Axes in the order aapt renders them, see ResTable_config::toString.
+const AXES: &[Axis] = &[
+    Axis {
+        field: Field::Imsi,
+        mask: 0x0000_ffff,
+        shift: 0,
+        format: Format::Numeric("mcc", ""),
+    },
+    Axis {
+        field: Field::Imsi,
+        mask: 0xffff_0000,
+        shift: 16,
+        format: Format::Mnc,
+    },
+    Axis {
+        field: Field::Locale,
+        mask: 0x0000_ffff,
+        shift: 0,
+        format: Format::Language,
+    },
+    Axis {
+        field: Field::Locale,
+        mask: 0xffff_0000,
+        shift: 16,
+        format: Format::Region,
+    },
+    Axis {
+        field: Field::ScreenConfig,
+        mask: 0x0000_00c0,
+        shift: 6,
+        format: Format::Named(LAYOUT_DIRS),
+    },
+    Axis {
+        field: Field::ScreenConfig,
+        mask: 0xffff_0000,
+        shift: 16,
+        format: Format::Numeric("sw", "dp"),
+    },
+    Axis {
+        field: Field::ScreenSizeDp,
+        mask: 0x0000_ffff,
+        shift: 0,
+        format: Format::Numeric("w", "dp"),
+    },
+    Axis {
+        field: Field::ScreenSizeDp,
+        mask: 0xffff_0000,
+        shift: 16,
+        format: Format::Numeric("h", "dp"),
+    },
+    Axis {
+        field: Field::ScreenConfig,
+        mask: 0x0000_000f,
+        shift: 0,
+        format: Format::Named(SCREEN_SIZES),
+    },
+    Axis {
+        field: Field::ScreenConfig,
+        mask: 0x0000_0030,
+        shift: 4,
+        format: Format::Named(SCREEN_LONG),
+    },
+    Axis {
+        field: Field::ScreenType,
+        mask: 0x0000_00ff,
+        shift: 0,
+        format: Format::Named(ORIENTATIONS),
+    },
+    Axis {
+        field: Field::ScreenConfig,
+        mask: 0x0000_0f00,
+        shift: 8,
+        format: Format::Named(UI_MODE_TYPES),
+    },
+    Axis {
+        field: Field::ScreenConfig,
+        mask: 0x0000_3000,
+        shift: 12,
+        format: Format::Named(UI_MODE_NIGHT),
+    },
+    Axis {
+        field: Field::ScreenType,
+        mask: 0xffff_0000,
+        shift: 16,
+        format: Format::Density,
+    },
+    Axis {
+        field: Field::ScreenType,
+        mask: 0x0000_ff00,
+        shift: 8,
+        format: Format::Named(TOUCHSCREENS),
+    },
+    Axis {
+        field: Field::Input,
+        mask: 0x0003_0000,
+        shift: 16,
+        format: Format::Named(KEYS_HIDDEN),
+    },
+    Axis {
+        field: Field::Input,
+        mask: 0x0000_00ff,
+        shift: 0,
+        format: Format::Named(KEYBOARDS),
+    },
+    Axis {
+        field: Field::Input,
+        mask: 0x000c_0000,
+        shift: 18,
+        format: Format::Named(NAV_HIDDEN),
+    },
+    Axis {
+        field: Field::Input,
+        mask: 0x0000_ff00,
+        shift: 8,
+        format: Format::Named(NAVIGATIONS),
+    },
+    Axis {
+        field: Field::ScreenSize,
+        mask: 0xffff_ffff,
+        shift: 0,
+        format: Format::ScreenSize,
+    },
+    Axis {
+        field: Field::Version,
+        mask: 0x0000_ffff,
+        shift: 0,
+        format: Format::Numeric("v", ""),
+    },
+];
+
+fn field(config: &ResourceConfiguration, field: Field) -> u32 {
+    match field {
+        Field::Imsi => config.imsi,
+        Field::Locale => config.locale,
+        Field::ScreenType => config.screen_type,
+        Field::Input => config.input,
+        Field::ScreenSize => config.screen_size,
+        Field::Version => config.version,
+        Field::ScreenConfig => config.screen_config,
+        Field::ScreenSizeDp => config.screen_size_dp,
+    }
+}
+
+fn field_mut(config: &mut ResourceConfiguration, field: Field) -> &mut u32 {
+    match field {
+        Field::Imsi => &mut config.imsi,
+        Field::Locale => &mut config.locale,
+        Field::ScreenType => &mut config.screen_type,
+        Field::Input => &mut config.input,
+        Field::ScreenSize => &mut config.screen_size,
+        Field::Version => &mut config.version,
+        Field::ScreenConfig => &mut config.screen_config,
+        Field::ScreenSizeDp => &mut config.screen_size_dp,
+    }
+}
+
+fn parse_number(s: &str, prefix: &str, suffix: &str) -> Option<u32> {
+    if !s.starts_with(prefix) || !s.ends_with(suffix) || s.len() < prefix.len() + suffix.len() {
+        return None;
+    }
+    let digits = &s[prefix.len()..s.len() - suffix.len()];
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn parse_name(s: &str, names: &[(u32, &str)]) -> Option<u32> {
+    names
+        .iter()
+        .find(|(_, name)| *name == s)
+        .map(|(value, _)| *value)
+}
+
+fn two_chars(value: u32) -> String {
+    [
+        (value & 0xff) as u8 as char,
+        ((value >> 8) & 0xff) as u8 as char,
+    ]
+    .iter()
+    .collect()
+}
+
+// Three letter languages and three digit regions are packed into two bytes, 5 bits per
+// character, with the high bit of the first byte set; see ResTable_config::packLanguageOrRegion.
+fn unpack_locale_part(value: u32, base: u8) -> String {
+    let (in0, in1) = ((value & 0xff) as u8, ((value >> 8) & 0xff) as u8);
+    if in0 & 0x80 == 0 {
+        return two_chars(value);
+    }
+    let first = in1 & 0x1f;
+    let second = ((in1 & 0xe0) >> 5) + ((in0 & 0x03) << 3);
+    let third = (in0 & 0x7c) >> 2;
+    [first, second, third]
+        .iter()
+        .map(|c| (c + base) as char)
+        .collect()
+}
+
+fn pack_locale_part(s: &str, base: u8) -> u32 {
+    let bytes = s.as_bytes();
+    if bytes.len() == 2 {
+        return bytes[0] as u32 | (bytes[1] as u32) << 8;
+    }
+    let (first, second, third) = (bytes[0] - base, bytes[1] - base, bytes[2] - base);
+    let in0 = 0x80 | (third << 2) | (second >> 3);
+    let in1 = (second << 5) | first;
+    in0 as u32 | (in1 as u32) << 8
+}
+
+fn nul_terminated(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|b| **b != 0)
+        .map(|b| *b as char)
+        .collect()
+}
+
+// Locales which cannot be expressed as "ll-rRR" are rendered in the BCP-47 based "b+" form.
+fn is_bcp47(config: &ResourceConfiguration) -> bool {
+    config.locale & 0x0080_0080 != 0
+        || config.locale_script != [0; 4]
+        || config.locale_variant != [0; 8]
+}
+
+fn parse_bcp47(tag: &str, config: &mut ResourceConfiguration) -> Option<()> {
+    let mut parts = tag.split('+').peekable();
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.bytes().all(|b| b.is_ascii_lowercase()) {
+        return None;
+    }
+    config.locale = pack_locale_part(language, b'a');
+
+    if let Some(script) =
+        parts.next_if(|p| p.len() == 4 && p.bytes().all(|b| b.is_ascii_alphabetic()))
+    {
+        config.locale_script.copy_from_slice(script.as_bytes());
+    }
+    if let Some(region) = parts.next_if(|p| {
+        (p.len() == 2 && p.bytes().all(|b| b.is_ascii_uppercase()))
+            || (p.len() == 3 && p.bytes().all(|b| b.is_ascii_digit()))
+    }) {
+        config.locale |= pack_locale_part(region, b'0') << 16;
+    }
+    if let Some(variant) = parts
+        .next_if(|p| (5..=8).contains(&p.len()) && p.bytes().all(|b| b.is_ascii_alphanumeric()))
+    {
+        config.locale_variant[..variant.len()].copy_from_slice(variant.as_bytes());
+    }
+    match parts.next() {
+        Some(_) => None,
+        None => Some(()),
+    }
+}
+
+fn parse_two_chars(s: &str, pred: fn(&u8) -> bool) -> Option<u32> {
+    let bytes = s.as_bytes();
+    if bytes.len() == 2 && bytes.iter().all(pred) {
+        Some(bytes[0] as u32 | (bytes[1] as u32) << 8)
+    } else {
+        None
+    }
+}
+
+// "<width>x<height>" in pixels, both non-zero
+fn parse_screen_size(s: &str) -> Option<u32> {
+    let (width, height) = s.split_once('x')?;
+    let width = parse_number(width, "", "")?;
+    let height = parse_number(height, "", "")?;
+    if width == 0 || width > 0xffff || height == 0 || height > 0xffff {
+        return None;
+    }
+    Some(height << 16 | width)
+}
+
+impl Axis {
+    fn value(&self, config: &ResourceConfiguration) -> u32 {
+        (field(config, self.field) & self.mask) >> self.shift
+    }
+
+    fn render(&self, config: &ResourceConfiguration) -> Option<String> {
+        let value = self.value(config);
+        if value == 0 {
+            return None;
+        }
+        let s = match self.format {
+            Format::Named(names) => match names.iter().find(|(v, _)| *v == value) {
+                Some((_, name)) => (*name).to_owned(),
+                None => format!("{:#x}", value),
+            },
+            Format::Numeric(prefix, suffix) => format!("{}{}{}", prefix, value, suffix),
+            Format::Mnc if value == MNC_ZERO => "mnc00".to_owned(),
+            Format::Mnc => format!("mnc{}", value),
+            Format::Language if is_bcp47(config) => {
+                format!("b+{}", config.locale()?.replace('-', "+"))
+            }
+            Format::Language => two_chars(value),
+            Format::Region if is_bcp47(config) => return None,
+            Format::Region => format!("r{}", two_chars(value)),
+            Format::Density => match DENSITIES.iter().find(|(v, _)| *v == value) {
+                Some((_, name)) => (*name).to_owned(),
+                None => format!("{}dpi", value),
+            },
+            Format::ScreenSize => {
+                format!("{}x{}", config.screen_width_px(), config.screen_height_px())
+            }
+        };
+        Some(s)
+    }
+
+    fn parse(&self, s: &str) -> Option<u32> {
+        let value = match self.format {
+            Format::Named(names) => parse_name(s, names),
+            Format::Numeric(prefix, suffix) => parse_number(s, prefix, suffix),
+            Format::Mnc if s == "mnc00" => Some(MNC_ZERO),
+            Format::Mnc => parse_number(s, "mnc", ""),
+            Format::Language => parse_two_chars(s, u8::is_ascii_lowercase),
+            Format::Region if s.starts_with('r') => {
+                parse_two_chars(&s[1..], u8::is_ascii_uppercase)
+            }
+            Format::Region => None,
+            Format::Density => parse_name(s, DENSITIES).or_else(|| parse_number(s, "", "dpi")),
+            Format::ScreenSize => parse_screen_size(s),
+        }?;
+        if value == 0 || value > self.mask >> self.shift {
+            return None;
+        }
+        Some(value)
+    }
+
+    fn set(&self, config: &mut ResourceConfiguration, value: u32) {
+        let field = field_mut(config, self.field);
+        *field = (*field & !self.mask) | (value << self.shift);
+    }
+}
+
+impl ResourceConfiguration {
+    /// The locale as a BCP-47 language tag, e.g. "en-US" or "zh-Hant-TW", or None if the
+    /// configuration does not specify a language.
+    pub fn locale(&self) -> Option<String> {
+        let language = self.locale & 0x0000_ffff;
+        if language == 0 {
+            return None;
+        }
+        let mut parts = vec![unpack_locale_part(language, b'a')];
+        if self.locale_script != [0; 4] {
+            parts.push(nul_terminated(&self.locale_script));
+        }
+        let region = self.locale >> 16;
+        if region != 0 {
+            parts.push(unpack_locale_part(region, b'0'));
+        }
+        if self.locale_variant != [0; 8] {
+            parts.push(nul_terminated(&self.locale_variant));
+        }
+        Some(parts.join("-"))
+    }
+
+    /// Render the configuration as an aapt style qualifier string, e.g. "en-rUS-hdpi-v21". The
+    /// default configuration is rendered as the empty string.
+    pub fn to_qualifier(&self) -> String {
+        AXES.iter()
+            .filter_map(|axis| axis.render(self))
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// Parse an aapt style qualifier string, e.g. "en-rUS-hdpi-v21", the inverse of
+    /// `to_qualifier`. The qualifiers must be given in the order aapt expects them.
+    pub fn parse_qualifier(s: &str) -> Result<ResourceConfiguration, Error> {
+        let mut config = ResourceConfiguration::default();
+        if s.is_empty() {
+            return Ok(config);
+        }
+
+        let language_axis = AXES
+            .iter()
+            .position(|axis| matches!(axis.format, Format::Language))
+            .unwrap();
+        let mut next_axis = 0;
+        for part in s.split('-') {
+            if let Some(tag) = part.strip_prefix("b+") {
+                if next_axis > language_axis {
+                    return Err(Error::InvalidInput(format!(
+                        "{}: qualifier {} out of order",
+                        s, part
+                    )));
+                }
+                parse_bcp47(tag, &mut config).ok_or_else(|| {
+                    Error::InvalidInput(format!("{}: bad locale qualifier {}", s, part))
+                })?;
+                next_axis = language_axis + 2; // the region is part of the tag
+                continue;
+            }
+
+            let found = AXES
+                .iter()
+                .enumerate()
+                .skip(next_axis)
+                .find_map(|(i, axis)| axis.parse(part).map(|value| (i, axis, value)));
+            match found {
+                Some((i, axis, value)) => {
+                    if let Format::Region = axis.format {
+                        if config.locale == 0 {
+                            return Err(Error::InvalidInput(format!(
+                                "{}: region qualifier {} without language",
+                                s, part
+                            )));
+                        }
+                    }
+                    axis.set(&mut config, value);
+                    next_axis = i + 1;
+                }
+                None if AXES.iter().any(|axis| axis.parse(part).is_some()) => {
+                    return Err(Error::InvalidInput(format!(
+                        "{}: qualifier {} out of order",
+                        s, part
+                    )));
+                }
+                None => {
+                    return Err(Error::InvalidInput(format!(
+                        "{}: unknown qualifier {}",
+                        s, part
+                    )));
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::ResourceConfiguration;
+
+    #[test]
+    fn parse_qualifier() {
+        let config = ResourceConfiguration::parse_qualifier("en-rUS-hdpi-v21").unwrap();
+        assert_eq!(config.locale, 0x5355_6e65);
+        assert_eq!(config.screen_type, 240 << 16);
+        assert_eq!(config.version, 21);
+
+        let config = ResourceConfiguration::parse_qualifier("").unwrap();
+        assert_eq!(config.to_qualifier(), "");
+    }
+
+    #[test]
+    fn parse_qualifier_round_trip() {
+        for s in &[
+            "sv",
+            "en-rXA",
+            "mcc310-mnc00",
+            "mcc310-mnc4-en-rGB",
+            "ldrtl-sw600dp-w960dp-h720dp",
+            "large-long-land-television-night",
+            "xxhdpi-v26",
+            "420dpi",
+            "anydpi-v26",
+            "finger-keyshidden-qwerty-navhidden-dpad-v13",
+            "hdpi-480x800-v4",
+        ] {
+            let config = ResourceConfiguration::parse_qualifier(s).unwrap();
+            assert_eq!(&config.to_qualifier(), s);
+        }
+    }
+
+    #[test]
+    fn locale() {
+        let config = ResourceConfiguration {
+            locale: 0x5754_687a, // zh, TW
+            locale_script: *b"Hant",
+            ..ResourceConfiguration::default()
+        };
+        assert_eq!(config.locale(), Some("zh-Hant-TW".to_owned()));
+        assert_eq!(config.to_qualifier(), "b+zh+Hant+TW");
+
+        let config = ResourceConfiguration::parse_qualifier("b+sr+Latn").unwrap();
+        assert_eq!(config.locale, 0x0000_7273);
+        assert_eq!(&config.locale_script, b"Latn");
+        assert_eq!(config.locale(), Some("sr-Latn".to_owned()));
+
+        let config = ResourceConfiguration::parse_qualifier("b+fil+PH-v21").unwrap();
+        assert_eq!(config.locale(), Some("fil-PH".to_owned()));
+
+        let config = ResourceConfiguration::parse_qualifier("b+es+419").unwrap();
+        assert_eq!(config.locale(), Some("es-419".to_owned()));
+
+        let config = ResourceConfiguration::parse_qualifier("en-rUS").unwrap();
+        assert_eq!(config.locale(), Some("en-US".to_owned()));
+        assert_eq!(ResourceConfiguration::default().locale(), None);
+
+        for s in &[
+            "b+zh+Hant+TW",
+            "b+sr+Latn-hdpi",
+            "b+ca+ES+valencia",
+            "b+fil",
+            "mcc310-b+es+419",
+        ] {
+            let config = ResourceConfiguration::parse_qualifier(s).unwrap();
+            assert_eq!(&config.to_qualifier(), s);
+        }
+
+        assert!(ResourceConfiguration::parse_qualifier("b+").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("b+en+US+foo+bar").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("b+en-rUS").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("hdpi-b+en").is_err());
+    }
+
+    #[test]
+    fn parse_qualifier_errors() {
+        assert!(ResourceConfiguration::parse_qualifier("foo").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("hdpi-en").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("v21-v22").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("rUS").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("en-").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("EN").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("v").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("v0").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("v65536").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("480x").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("0x800").is_err());
+        assert!(ResourceConfiguration::parse_qualifier("480x65536").is_err());
+    }
+}
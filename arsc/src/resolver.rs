@@ -0,0 +1,140 @@
+use crate::resources::{ResourceConfiguration, ResourceId, ResourceValue};
+use crate::table::LoadedTable;
+
+/// Several tables looked up as one, e.g. an app together with the framework and any runtime
+/// resource overlays. Each lookup is answered by the most recently added table that can answer
+/// it, so tables added later override the resources of tables added earlier.
+#[derive(Default)]
+pub struct ResourceResolver<'bytes> {
+    tables: Vec<LoadedTable<'bytes>>,
+}
+
+impl<'bytes> ResourceResolver<'bytes> {
+    pub fn new() -> ResourceResolver<'bytes> {
+        ResourceResolver::default()
+    }
+
+    /// Add a table; its resources take precedence over those of the tables already added.
+    pub fn add(&mut self, table: LoadedTable<'bytes>) {
+        self.tables.push(table);
+    }
+
+    /// The tables, in the order they were added.
+    pub fn tables(&self) -> &[LoadedTable<'bytes>] {
+        &self.tables
+    }
+
+    pub fn name_for_resid(&self, resid: &ResourceId) -> Option<(String, String, String)> {
+        self.tables_for(resid)
+            .find_map(|table| table.name_for_resid(resid))
+    }
+
+    pub fn value_for_resid(
+        &self,
+        resid: &ResourceId,
+    ) -> Option<Vec<(ResourceConfiguration, ResourceValue)>> {
+        self.tables_for(resid)
+            .find_map(|table| table.value_for_resid(resid))
+    }
+
+    pub fn resid_for_name(
+        &self,
+        package_name: &str,
+        type_name: &str,
+        entry_name: &str,
+    ) -> Option<ResourceId> {
+        self.tables
+            .iter()
+            .rev()
+            .find_map(|table| table.resid_for_name(package_name, type_name, entry_name))
+    }
+
+    // the tables with a package for the resource id, most recently added first
+    fn tables_for<'a>(
+        &'a self,
+        resid: &'a ResourceId,
+    ) -> impl Iterator<Item = &'a LoadedTable<'bytes>> + 'a {
+        self.tables
+            .iter()
+            .rev()
+            .filter(move |table| table.packages().any(|p| p.id() == resid.package_id()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResourceResolver;
+    use crate::resources::{ResourceId, ResourceValue};
+    use crate::table::LoadedTable;
+    use crate::test_utils;
+
+    fn table(package_id: u32, package_name: &str, keys: &[&str], values: &[u32]) -> Vec<u8> {
+        test_utils::table(
+            &[],
+            &[test_utils::package(
+                package_id,
+                package_name,
+                &["integer"],
+                keys,
+                &[test_utils::type_(
+                    1,
+                    &test_utils::default_config(),
+                    &values
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| {
+                            Some(test_utils::simple_entry(
+                                i as u32,
+                                test_utils::VALUE_INT_DEC,
+                                *value,
+                            ))
+                        })
+                        .collect::<Vec<_>>(),
+                )],
+            )],
+        )
+    }
+
+    #[test]
+    fn lookups() {
+        let framework = table(0x01, "android", &["config_foo"], &[1]);
+        let app = table(0x7f, "test.app", &["foo", "bar"], &[2, 3]);
+        let overlay = table(0x7f, "test.app", &["foo"], &[4]);
+
+        let mut resolver = ResourceResolver::new();
+        resolver.add(LoadedTable::parse(&framework).unwrap());
+        resolver.add(LoadedTable::parse(&app).unwrap());
+        resolver.add(LoadedTable::parse(&overlay).unwrap());
+        assert_eq!(resolver.tables().len(), 3);
+
+        let value = |resid| {
+            resolver
+                .value_for_resid(&ResourceId::from_u32(resid))
+                .map(|values| values.into_iter().map(|(_, v)| v).collect::<Vec<_>>())
+        };
+        assert_eq!(value(0x0101_0000), Some(vec![ResourceValue::IntDec(1)]));
+        assert_eq!(value(0x7f01_0000), Some(vec![ResourceValue::IntDec(4)]));
+        assert_eq!(value(0x7f01_0001), Some(vec![ResourceValue::IntDec(3)]));
+        assert_eq!(value(0x0201_0000), None);
+
+        assert_eq!(
+            resolver.name_for_resid(&ResourceId::from_u32(0x0101_0000)),
+            Some((
+                "android".to_owned(),
+                "integer".to_owned(),
+                "config_foo".to_owned()
+            ))
+        );
+        assert!(resolver
+            .name_for_resid(&ResourceId::from_u32(0x0201_0000))
+            .is_none());
+
+        assert_eq!(
+            resolver.resid_for_name("test.app", "integer", "bar"),
+            Some(ResourceId::from_u32(0x7f01_0001))
+        );
+        assert!(resolver
+            .resid_for_name("test.other", "integer", "foo")
+            .is_none());
+    }
+}
@@ -1,5 +1,9 @@
-use std::{convert, fmt};
+use crate::chunks::Configuration;
+use crate::error::Error;
+use num_enum::TryFromPrimitive;
+use std::{convert, fmt, iter, str};
 
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ResourceId {
     id: u32,
 }
@@ -10,6 +14,18 @@ impl convert::From<ResourceId> for u32 {
     }
 }
 
+impl PartialEq<u32> for ResourceId {
+    fn eq(&self, other: &u32) -> bool {
+        self.id == *other
+    }
+}
+
+impl PartialEq<ResourceId> for u32 {
+    fn eq(&self, other: &ResourceId) -> bool {
+        *self == other.id
+    }
+}
+
 impl ResourceId {
     pub fn from_parts(package_id: u8, type_id: u8, entry_id: u16) -> ResourceId {
         ResourceId {
@@ -21,6 +37,16 @@ impl ResourceId {
         ResourceId { id }
     }
 
+    pub fn to_le_bytes(&self) -> [u8; 4] {
+        self.id.to_le_bytes()
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 4]) -> ResourceId {
+        ResourceId {
+            id: u32::from_le_bytes(bytes),
+        }
+    }
+
     pub fn package_id(&self) -> u8 {
         ((self.id & 0xff00_0000) >> 24) as u8
     }
@@ -40,18 +66,51 @@ impl fmt::Debug for ResourceId {
     }
 }
 
-#[derive(Debug)]
+impl fmt::Display for ResourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#010x}", self.id)
+    }
+}
+
+/// Parse the canonical "0x7f020001" form: "0x" followed by exactly eight hex digits. To also
+/// accept resource names, see `LoadedTable::parse_resid`.
+impl str::FromStr for ResourceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad_input = || {
+            Error::InvalidInput(format!(
+                "bad resource id {:?}: expected 0x followed by 8 hex digits",
+                s
+            ))
+        };
+        let digits = s
+            .strip_prefix("0x")
+            .filter(|digits| digits.len() == 8 && digits.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(bad_input)?;
+        let id = u32::from_str_radix(digits, 16).map_err(|_| bad_input())?;
+        Ok(ResourceId { id })
+    }
+}
+
+// Arrays compare element by element, in order: for styles the order of the attributes is
+// significant (it is sorted by attribute id by aapt), so no reordering is done.
+#[derive(Clone, Debug, PartialEq)]
 pub enum ResourceValue {
     Null,
     Reference(ResourceId),
     Attribute(ResourceId),
     String(String),
     Float(f32),
-    Dimension(f32),
-    Fraction(f32),
+    Dimension(f32, DimensionUnit),
+    /// A fraction, e.g. 0.5 for "50%"; the unit says what it is a fraction of.
+    Fraction(f32, FractionUnit),
     IntDec(i32),
     IntHex(i32),
     Boolean(bool),
+    /// Color channels in [0, 1], in the order alpha, red, green, blue. The color channels are
+    /// straight, not premultiplied by alpha. The variants only differ in how the color was
+    /// written in the source (#aarrggbb, #rrggbb, #argb or #rgb).
     ColorArgb8(f32, f32, f32, f32),
     ColorRgb8(f32, f32, f32),
     ColorArgb4(f32, f32, f32, f32),
@@ -59,6 +118,114 @@ pub enum ResourceValue {
     Array(Vec<(ResourceId, ResourceValue)>),
 }
 
+/// The unit of a `ResourceValue::Dimension`, as stored in the low bits of the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum DimensionUnit {
+    Px = 0,
+    Dp = 1,
+    Sp = 2,
+    Pt = 3,
+    In = 4,
+    Mm = 5,
+}
+
+impl fmt::Display for DimensionUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self {
+            DimensionUnit::Px => "px",
+            DimensionUnit::Dp => "dp",
+            DimensionUnit::Sp => "sp",
+            DimensionUnit::Pt => "pt",
+            DimensionUnit::In => "in",
+            DimensionUnit::Mm => "mm",
+        };
+        write!(f, "{}", unit)
+    }
+}
+
+/// The unit of a `ResourceValue::Fraction`: a fraction of the item itself ("%") or of its
+/// parent container ("%p").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum FractionUnit {
+    Fraction = 0,
+    FractionParent = 1,
+}
+
+impl fmt::Display for FractionUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FractionUnit::Fraction => write!(f, "%"),
+            FractionUnit::FractionParent => write!(f, "%p"),
+        }
+    }
+}
+
+/// The decoded value of a complex entry. The keys of `entries` are attribute ids for styles and
+/// attrs; arrays use the keys 0x02000000 + index, and attr definitions and plurals use internal
+/// keys in package 0x01 type 0x00 (e.g. 0x01000000 for the attr type).
+#[derive(Debug, PartialEq)]
+pub struct ComplexValue {
+    /// The style this one inherits from, if any.
+    pub parent: Option<ResourceId>,
+    pub entries: Vec<(ResourceId, ResourceValue)>,
+}
+
+impl iter::FromIterator<(ResourceId, ResourceValue)> for ResourceValue {
+    fn from_iter<I: IntoIterator<Item = (ResourceId, ResourceValue)>>(iter: I) -> Self {
+        ResourceValue::Array(iter.into_iter().collect())
+    }
+}
+
+impl fmt::Display for ResourceValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // color components are stored as fractions in [0, 1]
+        let c8 = |c: f32| (c * 255.0).round() as u8;
+        let c4 = |c: f32| (c * 15.0).round() as u8;
+        match self {
+            ResourceValue::Null => write!(f, "@null"),
+            ResourceValue::Reference(resid) => write!(f, "@{}", resid),
+            ResourceValue::Attribute(resid) => write!(f, "?{}", resid),
+            ResourceValue::String(s) => write!(f, "{}", s),
+            ResourceValue::Float(v) => write!(f, "{}", v),
+            ResourceValue::Dimension(v, unit) => write!(f, "{}{}", v, unit),
+            ResourceValue::Fraction(v, unit) => write!(f, "{}{}", v * 100.0, unit),
+            ResourceValue::IntDec(v) => write!(f, "{}", v),
+            ResourceValue::IntHex(v) => write!(f, "{:#x}", v),
+            ResourceValue::Boolean(v) => write!(f, "{}", v),
+            ResourceValue::ColorArgb8(a, r, g, b) => {
+                write!(
+                    f,
+                    "#{:02x}{:02x}{:02x}{:02x}",
+                    c8(*a),
+                    c8(*r),
+                    c8(*g),
+                    c8(*b)
+                )
+            }
+            ResourceValue::ColorRgb8(r, g, b) => {
+                write!(f, "#{:02x}{:02x}{:02x}", c8(*r), c8(*g), c8(*b))
+            }
+            ResourceValue::ColorArgb4(a, r, g, b) => {
+                write!(f, "#{:x}{:x}{:x}{:x}", c4(*a), c4(*r), c4(*g), c4(*b))
+            }
+            ResourceValue::ColorRgb4(r, g, b) => write!(f, "#{:x}{:x}{:x}", c4(*r), c4(*g), c4(*b)),
+            ResourceValue::Array(items) => {
+                write!(f, "[")?;
+                for (i, (key, value)) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:#010x}={}", key.id, value)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ResourceConfiguration {
     #[allow(dead_code)]
     pub imsi: u32,
@@ -76,17 +243,90 @@ pub struct ResourceConfiguration {
     pub screen_config: u32,
     #[allow(dead_code)]
     pub screen_size_dp: u32,
+    pub locale_script: [u8; 4],
+    pub locale_variant: [u8; 8],
+}
+
+impl ResourceConfiguration {
+    pub fn is_default(&self) -> bool {
+        *self == ResourceConfiguration::default()
+    }
+
+    pub fn smallest_screen_width_dp(&self) -> u16 {
+        ((self.screen_config & 0xffff_0000) >> 16) as u16
+    }
+
+    /// The screen width in pixels of the deprecated "<width>x<height>" qualifier, 0 for any.
+    pub fn screen_width_px(&self) -> u16 {
+        (self.screen_size & 0x0000_ffff) as u16
+    }
+
+    /// The screen height in pixels of the deprecated "<width>x<height>" qualifier, 0 for any.
+    pub fn screen_height_px(&self) -> u16 {
+        ((self.screen_size & 0xffff_0000) >> 16) as u16
+    }
+
+    pub fn screen_width_dp(&self) -> u16 {
+        (self.screen_size_dp & 0x0000_ffff) as u16
+    }
+
+    pub fn screen_height_dp(&self) -> u16 {
+        ((self.screen_size_dp & 0xffff_0000) >> 16) as u16
+    }
+
+    /// True for anydpi-v26 and later: the configurations adaptive icons are defined in.
+    pub fn is_adaptive_icon_config(&self) -> bool {
+        const DENSITY_ANY: u32 = 0xfffe;
+        (self.screen_type >> 16) == DENSITY_ANY && (self.version & 0x0000_ffff) >= 26
+    }
+}
+
+impl convert::From<&Configuration> for ResourceConfiguration {
+    fn from(chunk: &Configuration) -> Self {
+        ResourceConfiguration {
+            imsi: chunk.imsi.value(),
+            locale: chunk.locale.value(),
+            screen_type: chunk.screen_type.value(),
+            input: chunk.input.value(),
+            screen_size: chunk.screen_size.value(),
+            version: chunk.version.value(),
+            screen_config: chunk.screen_config.value(),
+            screen_size_dp: chunk.screen_size_dp.value(),
+            // the locale script and variant were added after the original configuration layout
+            locale_script: if chunk.size() >= 0x28 {
+                chunk.locale_script
+            } else {
+                [0; 4]
+            },
+            locale_variant: if chunk.size() >= 0x30 {
+                chunk.locale_variant
+            } else {
+                [0; 8]
+            },
+        }
+    }
 }
 
-impl fmt::Debug for ResourceConfiguration {
+/// The qualifier string (see `to_qualifier`), or "default" for the default configuration. Use
+/// `{:?}` for the raw fields.
+impl fmt::Display for ResourceConfiguration {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "ResourceConfiguration {{ TODO(#10) }}")
+        if self.is_default() {
+            write!(f, "default")
+        } else {
+            write!(f, "{}", self.to_qualifier())
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ResourceId;
+    use super::{DimensionUnit, FractionUnit, ResourceConfiguration, ResourceId, ResourceValue};
+    use crate::error::Error;
+
+    fn default_config() -> ResourceConfiguration {
+        ResourceConfiguration::default()
+    }
 
     #[test]
     fn from_parts() {
@@ -102,4 +342,187 @@ mod tests {
         assert_eq!(resid.type_id(), 0x02);
         assert_eq!(resid.entry_id(), 0x0001);
     }
+
+    #[test]
+    fn le_bytes() {
+        let resid = ResourceId::from_u32(0x7f020001);
+        assert_eq!(resid.to_le_bytes(), [0x01, 0x00, 0x02, 0x7f]);
+        assert_eq!(ResourceId::from_le_bytes(resid.to_le_bytes()), resid);
+    }
+
+    #[test]
+    fn resid_from_str() {
+        let resid = "0x7f020001".parse::<ResourceId>().unwrap();
+        assert_eq!(resid, 0x7f020001);
+        assert_eq!(resid.to_string(), "0x7f020001");
+        assert_eq!(ResourceId::from_u32(0x01).to_string(), "0x00000001");
+        assert_eq!("0x7F02000a".parse::<ResourceId>().unwrap(), 0x7f02000a);
+
+        for s in &[
+            "",
+            "0x",
+            "7f020001",
+            "0x7f0201",
+            "0x7f0200010",
+            "0x7f02000g",
+            "0x+f020001",
+        ] {
+            match s.parse::<ResourceId>() {
+                Err(Error::InvalidInput(_)) => {}
+                _ => panic!("expected Error::InvalidInput for {:?}", s),
+            }
+        }
+    }
+
+    #[test]
+    fn eq_u32() {
+        let resid = ResourceId::from_u32(0x7f020001);
+        assert_eq!(resid, 0x7f020001);
+        assert_eq!(0x7f020001, resid);
+        assert_ne!(resid, 0x7f020002);
+        assert_ne!(0x7f020002, resid);
+    }
+
+    #[test]
+    fn screen_dp_qualifiers() {
+        let config = default_config();
+        assert_eq!(config.to_qualifier(), "");
+
+        let config = ResourceConfiguration {
+            screen_config: 600 << 16,
+            ..default_config()
+        };
+        assert_eq!(config.smallest_screen_width_dp(), 600);
+        assert_eq!(config.to_qualifier(), "sw600dp");
+
+        let config = ResourceConfiguration {
+            screen_config: 320 << 16,
+            screen_size_dp: 1024 << 16 | 600,
+            ..default_config()
+        };
+        assert_eq!(config.screen_width_dp(), 600);
+        assert_eq!(config.screen_height_dp(), 1024);
+        assert_eq!(config.to_qualifier(), "sw320dp-w600dp-h1024dp");
+    }
+
+    #[test]
+    fn screen_px_qualifier() {
+        let config = ResourceConfiguration {
+            screen_size: 800 << 16 | 480,
+            version: 4,
+            ..default_config()
+        };
+        assert_eq!(config.screen_width_px(), 480);
+        assert_eq!(config.screen_height_px(), 800);
+        assert_eq!(config.to_qualifier(), "480x800-v4");
+        assert_eq!(
+            ResourceConfiguration::parse_qualifier("480x800-v4").unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn display_config() {
+        assert_eq!(default_config().to_string(), "default");
+        let config = ResourceConfiguration::parse_qualifier("en-rUS-sw600dp-xxhdpi-v26").unwrap();
+        assert_eq!(config.to_string(), "en-rUS-sw600dp-xxhdpi-v26");
+    }
+
+    #[test]
+    fn debug_config() {
+        let config = ResourceConfiguration::parse_qualifier("v21").unwrap();
+        assert_eq!(
+            format!("{:?}", config),
+            "ResourceConfiguration { imsi: 0, locale: 0, screen_type: 0, input: 0, \
+             screen_size: 0, version: 21, screen_config: 0, screen_size_dp: 0, \
+             locale_script: [0, 0, 0, 0], locale_variant: [0, 0, 0, 0, 0, 0, 0, 0] }"
+        );
+    }
+
+    #[test]
+    fn is_adaptive_icon_config() {
+        let parse = |s| ResourceConfiguration::parse_qualifier(s).unwrap();
+        assert!(parse("anydpi-v26").is_adaptive_icon_config());
+        assert!(parse("anydpi-v28").is_adaptive_icon_config());
+        assert!(!parse("anydpi-v21").is_adaptive_icon_config());
+        assert!(!parse("anydpi").is_adaptive_icon_config());
+        assert!(!parse("xxhdpi-v26").is_adaptive_icon_config());
+        assert!(!default_config().is_adaptive_icon_config());
+    }
+
+    #[test]
+    fn display_value() {
+        assert_eq!(ResourceValue::Null.to_string(), "@null");
+        assert_eq!(
+            ResourceValue::Reference(ResourceId::from_u32(0x7f01_0000)).to_string(),
+            "@0x7f010000"
+        );
+        assert_eq!(ResourceValue::String("Foo".to_owned()).to_string(), "Foo");
+        assert_eq!(ResourceValue::IntDec(-3).to_string(), "-3");
+        assert_eq!(ResourceValue::IntHex(0xff).to_string(), "0xff");
+        assert_eq!(ResourceValue::Boolean(true).to_string(), "true");
+        assert_eq!(
+            ResourceValue::ColorArgb8(1.0, 1.0, 0.0, 0.5).to_string(),
+            "#ffff0080"
+        );
+        assert_eq!(ResourceValue::ColorRgb4(1.0, 0.0, 0.0).to_string(), "#f00");
+        assert_eq!(
+            ResourceValue::Dimension(16.0, DimensionUnit::Dp).to_string(),
+            "16dp"
+        );
+        assert_eq!(
+            ResourceValue::Fraction(0.5, FractionUnit::FractionParent).to_string(),
+            "50%p"
+        );
+        assert_eq!(
+            ResourceValue::Array(vec![
+                (ResourceId::from_u32(0x0101_0000), ResourceValue::IntDec(1)),
+                (
+                    ResourceId::from_u32(0x0101_0001),
+                    ResourceValue::Boolean(false)
+                ),
+            ])
+            .to_string(),
+            "[0x01010000=1, 0x01010001=false]"
+        );
+    }
+
+    #[test]
+    fn collect_array() {
+        let value: ResourceValue = vec![
+            (ResourceId::from_u32(0x0101_0000), ResourceValue::IntDec(1)),
+            (ResourceId::from_u32(0x0101_0001), ResourceValue::IntDec(2)),
+        ]
+        .into_iter()
+        .collect();
+        match value {
+            ResourceValue::Array(items) => {
+                assert_eq!(items.len(), 2);
+                assert_eq!(items[0].0, 0x0101_0000);
+                assert_eq!(items[1].0, 0x0101_0001);
+            }
+            _ => panic!("expected an array"),
+        }
+
+        let value: ResourceValue = Vec::new().into_iter().collect();
+        assert_eq!(value.to_string(), "[]");
+    }
+
+    #[test]
+    fn eq_array() {
+        let style = |attrs: &[(u32, i32)]| {
+            attrs
+                .iter()
+                .map(|(k, v)| (ResourceId::from_u32(*k), ResourceValue::IntDec(*v)))
+                .collect::<ResourceValue>()
+        };
+        let a = style(&[(0x0101_0000, 1), (0x0101_0001, 2)]);
+        assert_eq!(a, style(&[(0x0101_0000, 1), (0x0101_0001, 2)]));
+        assert_ne!(a, style(&[(0x0101_0000, 1), (0x0101_0001, 3)]));
+        assert_ne!(
+            a,
+            style(&[(0x0101_0000, 1), (0x0101_0001, 2), (0x0101_0002, 3)])
+        );
+        assert_ne!(a, style(&[(0x0101_0001, 2), (0x0101_0000, 1)]));
+    }
 }
@@ -1,8 +1,9 @@
-use crate::chunks::{Chunk, StringPool, StringPoolSpan};
-use crate::endianness::LittleEndianU32;
+use crate::chunks::{Chunk, ChunkIterator, StringPool};
+use crate::endianness::{LittleEndianU16, LittleEndianU32};
 use crate::error::Error;
+use std::collections::HashMap;
 use std::mem;
-use std::slice;
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum Encoding {
@@ -10,21 +11,31 @@ pub enum Encoding {
     Utf16,
 }
 
+// All offsets are relative to the start of the string pool chunk, `bytes`.
 #[derive(Debug)]
 pub struct LoadedStringPool<'bytes> {
+    bytes: &'bytes [u8],
     encoding: Encoding,
+    sorted: bool,
 
     string_count: usize,
-    string_offsets: &'bytes [LittleEndianU32],
-    strings_start: *const u8,
+    string_offsets: usize,
+    strings_start: usize,
 
     style_count: usize,
-    style_offsets: Option<&'bytes [LittleEndianU32]>,
-    styles_start: Option<*const u8>,
+    style_offsets: usize,
+    styles_start: usize,
+
+    // decoded strings, indexed by string index; None unless enabled with enable_cache
+    cache: Option<Mutex<HashMap<usize, Arc<str>>>>,
 }
 
 impl<'bytes> LoadedStringPool<'bytes> {
     pub fn from_chunk(chunk: Chunk<'bytes>) -> Result<LoadedStringPool<'bytes>, Error> {
+        let bytes = match chunk {
+            Chunk::StringPool(bytes) => bytes,
+            _ => return Err(Error::UnexpectedChunk),
+        };
         let details = chunk.as_stringpool()?;
 
         if details.header.size.value() < mem::size_of::<StringPool>() as u32 {
@@ -44,55 +55,112 @@ impl<'bytes> LoadedStringPool<'bytes> {
             return Err(Error::CorruptData("misaligned data".to_owned()));
         }
 
-        let base_addr: usize = unsafe { mem::transmute(details) };
-        let addr = base_addr + details.header.header_size.value() as usize;
-
-        let string_offsets: &[LittleEndianU32] = unsafe {
-            slice::from_raw_parts(
-                addr as *const LittleEndianU32,
-                details.string_count.value() as usize,
-            )
-        };
-
-        let addr = base_addr + details.strings_offset.value() as usize;
-        let strings_start = addr as *const u8;
-
+        let string_count = details.string_count.value() as usize;
+        let string_offsets = details.header.header_size.value() as usize;
         let style_count = details.style_count.value() as usize;
-        let mut style_offsets: Option<&[LittleEndianU32]> = None;
-        let styles_start: Option<*const u8> = if style_count != 0 {
-            let addr = base_addr
-                + details.header.header_size.value() as usize
-                + details.string_count.value() as usize * mem::size_of::<LittleEndianU32>();
-            style_offsets =
-                Some(unsafe { slice::from_raw_parts(addr as *const LittleEndianU32, style_count) });
-
-            Some((base_addr + details.styles_offset.value() as usize) as *const u8)
-        } else {
-            None
-        };
+        let style_offsets = string_offsets + string_count * mem::size_of::<LittleEndianU32>();
+        let offsets_end = style_offsets + style_count * mem::size_of::<LittleEndianU32>();
+        if offsets_end > bytes.len() {
+            return Err(Error::CorruptData(format!(
+                "{} string and {} style offsets do not fit in {} bytes",
+                string_count,
+                style_count,
+                bytes.len()
+            )));
+        }
 
         Ok(LoadedStringPool {
+            bytes,
             encoding: if details.flags.value() & (1 << 8) != 0 {
                 Encoding::Utf8
             } else {
                 Encoding::Utf16
             },
+            sorted: details.flags.value() & 0x01 != 0,
 
-            string_count: details.string_count.value() as usize,
+            string_count,
             string_offsets,
-            strings_start,
+            strings_start: details.strings_offset.value() as usize,
 
             style_count,
             style_offsets,
-            styles_start,
+            styles_start: details.styles_offset.value() as usize,
+
+            cache: None,
         })
     }
 
-    #[allow(dead_code)]
+    /// Parse a string pool chunk on its own, e.g. the pool embedded in a binary XML file.
+    pub fn from_bytes(bytes: &'bytes [u8]) -> Result<LoadedStringPool<'bytes>, Error> {
+        match ChunkIterator::new(bytes).next() {
+            Some(Chunk::StringPool(b)) => LoadedStringPool::from_chunk(Chunk::StringPool(b)),
+            Some(Chunk::Error(msg)) => Err(Error::CorruptData(msg)),
+            Some(_) => Err(Error::UnexpectedChunk),
+            None => Err(Error::CorruptData("no data to traverse".to_owned())),
+        }
+    }
+
+    pub fn is_utf8(&self) -> bool {
+        self.encoding == Encoding::Utf8
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// A description of each of the string and style data offsets that is not 4-byte aligned.
+    pub fn misaligned_offsets(&self) -> Vec<String> {
+        let mut v = Vec::new();
+        if self.strings_start & 0x03 != 0 {
+            v.push(format!(
+                "strings offset {:#x} not 4-byte aligned",
+                self.strings_start
+            ));
+        }
+        if self.style_count > 0 && self.styles_start & 0x03 != 0 {
+            v.push(format!(
+                "styles offset {:#x} not 4-byte aligned",
+                self.styles_start
+            ));
+        }
+        v
+    }
+
     pub fn string_count(&self) -> usize {
         self.string_count
     }
 
+    pub fn strings(&self) -> impl Iterator<Item = Result<String, Error>> + '_ {
+        (0..self.string_count).map(move |i| self.string_at(i))
+    }
+
+    /// Keep every string decoded by `cached_string_at` for the lifetime of the pool, so repeated
+    /// lookups of the same index only pay for the decoding once. Off by default: the cache grows
+    /// with each distinct index looked up. `LoadedTable` decodes its type and key names once while
+    /// parsing and does not use the cache; it is meant for callers holding on to a pool of their
+    /// own, e.g. one read with `from_bytes`.
+    pub fn enable_cache(&mut self) {
+        if self.cache.is_none() {
+            self.cache = Some(Mutex::new(HashMap::new()));
+        }
+    }
+
+    /// Like `string_at`, but shared: if the cache is enabled, the string is decoded once and
+    /// later lookups of the same index return the cached copy without allocating.
+    pub fn cached_string_at(&self, i: usize) -> Result<Arc<str>, Error> {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return Ok(self.string_at(i)?.into()),
+        };
+        if let Some(s) = cache.lock().unwrap().get(&i) {
+            return Ok(Arc::clone(s));
+        }
+        let s: Arc<str> = self.string_at(i)?.into();
+        cache.lock().unwrap().insert(i, Arc::clone(&s));
+        Ok(s)
+    }
+
+    /// Decode the i'th string from the underlying bytes, whether the cache is enabled or not.
     pub fn string_at(&self, i: usize) -> Result<String, Error> {
         if i >= self.string_count {
             return Err(Error::BadIndex);
@@ -103,84 +171,124 @@ impl<'bytes> LoadedStringPool<'bytes> {
         }
     }
 
-    #[allow(dead_code)]
     pub fn style_count(&self) -> usize {
         self.style_count
     }
 
-    #[allow(dead_code)]
     pub fn style_at(&self, i: usize) -> Result<Vec<LoadedStringPoolSpan>, Error> {
         if i >= self.style_count {
             return Err(Error::BadIndex);
         }
 
-        let style_offsets = self.style_offsets.unwrap();
-        let styles_start = self.styles_start.unwrap();
         let mut spans = Vec::new();
-
-        let offset = style_offsets[i].value() as usize;
-        unsafe {
-            #[allow(clippy::cast_ptr_alignment)]
-            let mut span_ptr = styles_start.add(offset) as *const StringPoolSpan;
-            while (*span_ptr).name.value() != 0xffff_ffff {
-                spans.push(LoadedStringPoolSpan {
-                    name: (*span_ptr).name.value(),
-                    begin: (*span_ptr).begin.value(),
-                    end: (*span_ptr).end.value(),
-                });
-                span_ptr = span_ptr.add(1);
+        let mut offset = self.styles_start + self.read_u32(self.style_offsets + i * 4)? as usize;
+        loop {
+            let name = self.read_u32(offset)?;
+            if name == 0xffff_ffff {
+                break;
             }
+            spans.push(LoadedStringPoolSpan {
+                name,
+                begin: self.read_u32(offset + 4)?,
+                end: self.read_u32(offset + 8)?,
+            });
+            offset += 12;
         }
         Ok(spans)
     }
 
+    /// The i'th string with the tag names of its style spans resolved; strings without a style
+    /// have no spans.
+    pub fn styled_string_at(&self, i: usize) -> Result<StyledString, Error> {
+        let text = self.string_at(i)?;
+        if i >= self.style_count {
+            return Ok(StyledString {
+                text,
+                spans: Vec::new(),
+            });
+        }
+        let spans = self
+            .style_at(i)?
+            .into_iter()
+            .map(|span| Ok((self.string_at(span.name as usize)?, span.begin, span.end)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(StyledString { text, spans })
+    }
+
+    fn read_u32(&self, offset: usize) -> Result<u32, Error> {
+        LittleEndianU32::read_at(self.bytes, offset)
+            .ok_or_else(|| Error::CorruptData(format!("offset {:#x} out of bounds", offset)))
+    }
+
+    fn read_u16(&self, offset: usize) -> Result<u16, Error> {
+        LittleEndianU16::read_at(self.bytes, offset)
+            .ok_or_else(|| Error::CorruptData(format!("offset {:#x} out of bounds", offset)))
+    }
+
+    fn read_u8(&self, offset: usize) -> Result<u8, Error> {
+        self.bytes
+            .get(offset)
+            .copied()
+            .ok_or_else(|| Error::CorruptData(format!("offset {:#x} out of bounds", offset)))
+    }
+
+    fn string_offset(&self, i: usize) -> Result<usize, Error> {
+        let offset = self.read_u32(self.string_offsets + i * 4)? as usize;
+        Ok(self.strings_start + offset)
+    }
+
     fn string_at_utf8(&self, i: usize) -> Result<String, Error> {
-        unsafe fn decode_len(ptr: *const u8) -> (usize, usize) {
-            let mut len = *ptr as usize;
+        let decode_len = |offset: usize| -> Result<(usize, usize), Error> {
+            let len = self.read_u8(offset)? as usize;
             if (len & 0x80) != 0 {
-                len = (len & 0x7f) << 8 | *ptr.add(1) as usize;
-                (2, len)
+                let len = (len & 0x7f) << 8 | self.read_u8(offset + 1)? as usize;
+                Ok((2, len))
             } else {
-                (1, len)
+                Ok((1, len))
             }
-        }
+        };
 
-        unsafe {
-            let offset = self.string_offsets[i].value() as usize;
-            let string_ptr = self.strings_start.add(offset) as *const u8;
+        let offset = self.string_offset(i)?;
 
-            // length is encoded twice, so fast forward over the first instance
-            let (bump, _) = decode_len(string_ptr);
-            let string_ptr = string_ptr.add(bump);
+        // the length is encoded twice, first in UTF-16 code units, then in UTF-8 bytes; each is
+        // one or two bytes long on its own, so fast forward over the first instance
+        let (bump, _) = decode_len(offset)?;
+        let offset = offset + bump;
 
-            let (bump, len) = decode_len(string_ptr);
-            let slice = slice::from_raw_parts(string_ptr.add(bump), len);
+        let (bump, len) = decode_len(offset)?;
+        let offset = offset + bump;
+        let slice = self
+            .bytes
+            .get(offset..offset + len)
+            .ok_or_else(|| Error::CorruptData(format!("string {} out of bounds", i)))?;
 
-            Ok(String::from_utf8_lossy(slice).to_string())
-        }
+        Ok(String::from_utf8_lossy(slice).to_string())
     }
 
     fn string_at_utf16(&self, i: usize) -> Result<String, Error> {
-        unsafe fn decode_len(ptr: *const u16) -> (usize, usize) {
-            let mut len = *ptr as usize;
+        let decode_len = |offset: usize| -> Result<(usize, usize), Error> {
+            let len = self.read_u16(offset)? as usize;
             if (len & 0x8000) != 0 {
-                len = (len & 0x7fff) << 16 | *ptr.add(1) as usize;
-                (2, len)
+                let len = (len & 0x7fff) << 16 | self.read_u16(offset + 2)? as usize;
+                Ok((4, len))
             } else {
-                (1, len)
+                Ok((2, len))
             }
-        }
-
-        unsafe {
-            let offset = self.string_offsets[i].value() as usize;
-            #[allow(clippy::cast_ptr_alignment)]
-            let string_ptr = self.strings_start.add(offset) as *const u16;
-
-            let (bump, len) = decode_len(string_ptr);
-            let slice = slice::from_raw_parts(string_ptr.add(bump), len);
+        };
 
-            Ok(String::from_utf16_lossy(slice))
-        }
+        let offset = self.string_offset(i)?;
+        let (bump, len) = decode_len(offset)?;
+        let offset = offset + bump;
+        let slice = self
+            .bytes
+            .get(offset..offset + len * 2)
+            .ok_or_else(|| Error::CorruptData(format!("string {} out of bounds", i)))?;
+        let units = slice
+            .chunks_exact(2)
+            .map(|ch| u16::from_le_bytes([ch[0], ch[1]]))
+            .collect::<Vec<_>>();
+
+        Ok(String::from_utf16_lossy(&units))
     }
 }
 
@@ -191,10 +299,21 @@ pub struct LoadedStringPoolSpan {
     pub end: u32,
 }
 
+/// A string and its style spans as (tag, first char, last char), e.g. ("b", 0, 3) for a string
+/// whose first four characters are bold. Tags may carry attributes: "font;color=#ff0000".
+#[derive(Debug, PartialEq, Eq)]
+pub struct StyledString {
+    pub text: String,
+    pub spans: Vec<(String, u32, u32)>,
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Encoding, LoadedStringPool};
+    use super::{Encoding, LoadedStringPool, StyledString};
     use crate::chunks::{Chunk, ChunkIterator};
+    use crate::test_utils;
+    use std::sync::Arc;
+    use std::time::Instant;
 
     const RESOURCE_ARSC: &[u8] = include_bytes!("../../tests/data/unpacked/resources.arsc");
 
@@ -243,4 +362,148 @@ mod tests {
         assert_eq!(sp.string_at(1).unwrap(), "string".to_string());
         assert!(sp.string_at(2).is_err());
     }
+
+    #[test]
+    fn decode_utf8_multi_byte() {
+        // "ö" is one UTF-16 code unit but two UTF-8 bytes: the lengths differ, and for the long
+        // string only the byte count needs two bytes
+        let long = "ö".repeat(100);
+        let bytes = test_utils::string_pool(&["Föö", &long, "bar"], true);
+        let sp = LoadedStringPool::from_bytes(&bytes).unwrap();
+        assert_eq!(sp.string_at(0).unwrap(), "Föö".to_owned());
+        assert_eq!(sp.string_at(1).unwrap(), long);
+        assert_eq!(sp.string_at(2).unwrap(), "bar".to_owned());
+    }
+
+    #[test]
+    fn from_bytes() {
+        let bytes = test_utils::string_pool(&["foo", "bar"], true);
+        let sp = LoadedStringPool::from_bytes(&bytes).unwrap();
+        assert_eq!(sp.string_count(), 2);
+        assert_eq!(sp.string_at(0).unwrap(), "foo".to_owned());
+        assert_eq!(sp.string_at(1).unwrap(), "bar".to_owned());
+        assert_eq!(
+            sp.strings().collect::<Result<Vec<_>, _>>().unwrap(),
+            vec!["foo".to_owned(), "bar".to_owned()]
+        );
+
+        assert!(LoadedStringPool::from_bytes(RESOURCE_ARSC).is_err()); // table, not string pool
+        assert!(LoadedStringPool::from_bytes(&bytes[..4]).is_err());
+        assert!(LoadedStringPool::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn misaligned_data() {
+        let bytes = test_utils::string_pool(&["foo", "bär"], false);
+        let mut buf = vec![0u8; bytes.len() + 1];
+        buf[1..].copy_from_slice(&bytes);
+        let sp = LoadedStringPool::from_bytes(&buf[1..]).unwrap();
+        assert_eq!(sp.string_at(0).unwrap(), "foo".to_owned());
+        assert_eq!(sp.string_at(1).unwrap(), "bär".to_owned());
+    }
+
+    #[test]
+    fn styled_strings() {
+        for &utf8 in &[true, false] {
+            let bytes = test_utils::styled_string_pool(
+                &["Hello World", "plain", "b", "i"],
+                &[&[(2, 0, 4), (3, 6, 10)], &[]],
+                utf8,
+            );
+            let sp = LoadedStringPool::from_bytes(&bytes).unwrap();
+            assert_eq!(sp.style_count(), 2);
+            assert_eq!(
+                sp.strings().collect::<Result<Vec<_>, _>>().unwrap(),
+                vec!["Hello World", "plain", "b", "i"]
+            );
+            assert_eq!(
+                sp.styled_string_at(0).unwrap(),
+                StyledString {
+                    text: "Hello World".to_owned(),
+                    spans: vec![("b".to_owned(), 0, 4), ("i".to_owned(), 6, 10)],
+                }
+            );
+            assert!(sp.styled_string_at(1).unwrap().spans.is_empty());
+            assert!(sp.styled_string_at(3).unwrap().spans.is_empty());
+            assert!(sp.styled_string_at(4).is_err());
+        }
+    }
+
+    #[test]
+    fn misaligned_offsets() {
+        let mut bytes = test_utils::string_pool(&["foo", "bar"], true);
+        let sp = LoadedStringPool::from_bytes(&bytes).unwrap();
+        assert!(sp.misaligned_offsets().is_empty());
+
+        // strings_offset: the data now starts one byte into the first string
+        bytes[20] += 1;
+        let sp = LoadedStringPool::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            sp.misaligned_offsets(),
+            vec!["strings offset 0x25 not 4-byte aligned".to_owned()]
+        );
+    }
+
+    #[test]
+    fn string_at_cache() {
+        fn is_sync<T: Sync>() {}
+        is_sync::<LoadedStringPool>();
+
+        let bytes = test_utils::string_pool(&["foo", "bar"], true);
+        let mut sp = LoadedStringPool::from_bytes(&bytes).unwrap();
+        assert!(sp.cache.is_none());
+        assert_eq!(&*sp.cached_string_at(1).unwrap(), "bar");
+        assert!(sp.cache.is_none());
+
+        sp.enable_cache();
+        let cached_len = |sp: &LoadedStringPool| sp.cache.as_ref().unwrap().lock().unwrap().len();
+        let first = sp.cached_string_at(1).unwrap();
+        let second = sp.cached_string_at(1).unwrap();
+        assert_eq!(&*first, "bar");
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(cached_len(&sp), 1);
+
+        assert_eq!(sp.string_at(0).unwrap(), "foo".to_owned());
+        assert_eq!(cached_len(&sp), 1);
+
+        assert!(sp.cached_string_at(2).is_err());
+        assert_eq!(cached_len(&sp), 1);
+    }
+
+    // cargo test --release -p arsc -- --ignored --nocapture string_at_cache_timing
+    #[test]
+    #[ignore]
+    fn string_at_cache_timing() {
+        let strings = (0..1000)
+            .map(|i| format!("resource_name_{:04}_with_a_realistic_length", i))
+            .collect::<Vec<_>>();
+        let strings = strings.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        for utf8 in [true, false].iter() {
+            let bytes = test_utils::string_pool(&strings, *utf8);
+            let mut sp = LoadedStringPool::from_bytes(&bytes).unwrap();
+            sp.enable_cache();
+            let lookups = || (0..100).flat_map(|_| 0..strings.len());
+
+            let start = Instant::now();
+            let total = lookups()
+                .map(|i| sp.string_at(i).unwrap().len())
+                .sum::<usize>();
+            let uncached = start.elapsed();
+
+            let start = Instant::now();
+            let cached_total = lookups()
+                .map(|i| sp.cached_string_at(i).unwrap().len())
+                .sum::<usize>();
+            let cached = start.elapsed();
+
+            assert_eq!(total, cached_total);
+            println!(
+                "utf8={}: {} lookups, string_at {:?}, cached_string_at {:?}",
+                utf8,
+                100 * strings.len(),
+                uncached,
+                cached
+            );
+        }
+    }
 }
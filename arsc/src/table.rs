@@ -1,12 +1,16 @@
 use crate::chunks::{
-    Chunk, ChunkIterator, Configuration, Entry, KeyAndValue, MapEntry, Spec, Value, ValueType,
+    view, view_slice, Chunk, ChunkIterator, Configuration, ConfigurationFlags, Entry, EntryFlags,
+    KeyAndValue, LibraryEntry, MapEntry, Package, Spec, StringPool, Type, Value, ValueType,
 };
 use crate::endianness::{LittleEndianU16, LittleEndianU32};
 use crate::error::Error;
-use crate::resources::{ResourceConfiguration, ResourceId, ResourceValue};
-use crate::stringpool::LoadedStringPool;
-use std::collections::HashMap;
-use std::convert::TryInto;
+use crate::owned::OwnedTable;
+use crate::resources::{
+    ComplexValue, DimensionUnit, FractionUnit, ResourceConfiguration, ResourceId, ResourceValue,
+};
+use crate::stringpool::{LoadedStringPool, StyledString};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::{TryFrom, TryInto};
 use std::mem;
 use std::slice;
 
@@ -16,8 +20,17 @@ pub enum LoadedValue<'bytes> {
     Complex(&'bytes MapEntry, &'bytes [KeyAndValue]),
 }
 
+impl<'bytes> LoadedValue<'bytes> {
+    fn flags(&self) -> EntryFlags {
+        match self {
+            LoadedValue::Single(entry, _) => entry.flags(),
+            LoadedValue::Complex(map_entry, _) => map_entry.entry.flags(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-struct ConfigAndValue<'bytes>(&'bytes Configuration, LoadedValue<'bytes>);
+struct ConfigAndValue<'bytes>(ResourceConfiguration, LoadedValue<'bytes>);
 
 #[derive(Debug)]
 struct LoadedEntry<'bytes> {
@@ -29,51 +42,363 @@ struct LoadedEntry<'bytes> {
 #[derive(Debug)]
 struct LoadedType<'bytes> {
     id: u8,
-    name: String,
+    // one CONFIG_* bitmask per entry, or None if the type has no spec chunk
+    spec_flags: Option<Vec<u32>>,
     entries: Vec<LoadedEntry<'bytes>>,
 }
 
+#[derive(Debug, Default)]
+struct TypeRegistry {
+    names: HashMap<u8, String>,
+    ids: HashMap<String, u8>,
+}
+
+impl TypeRegistry {
+    fn insert(&mut self, id: u8, name: String) {
+        self.ids.insert(name.clone(), id);
+        self.names.insert(id, name);
+    }
+
+    fn name(&self, id: u8) -> Option<&str> {
+        self.names.get(&id).map(|name| name.as_str())
+    }
+
+    fn id(&self, name: &str) -> Option<u8> {
+        self.ids.get(name).copied()
+    }
+
+    fn rename(&mut self, old: &str, new: &str) -> Option<()> {
+        let id = self.ids.remove(old)?;
+        self.insert(id, new.to_owned());
+        Some(())
+    }
+}
+
 struct LoadedPackage<'bytes> {
     id: u8,
     name: String,
-    #[allow(dead_code)]
+    is_dynamic_library: bool,
+    // build time package id -> package name of each shared library the package references
+    libraries: Vec<(u8, String)>,
     type_strings: LoadedStringPool<'bytes>,
-    #[allow(dead_code)]
     name_strings: LoadedStringPool<'bytes>,
+    type_registry: TypeRegistry,
     types: Vec<LoadedType<'bytes>>,
 }
 
+impl<'bytes> LoadedPackage<'bytes> {
+    fn type_by_name(&self, name: &str) -> Option<&LoadedType<'bytes>> {
+        let id = self.type_registry.id(name)?;
+        self.types.iter().find(|t| t.id == id)
+    }
+}
+
+/// The string pools of a table: the global value pool, and each package's type and key pools
+/// (identified by package id).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolKind {
+    Value,
+    Type(u8),
+    Key(u8),
+}
+
+/// A read-only view of a package in a `LoadedTable`.
+pub struct PackageRef<'a, 'bytes> {
+    package: &'a LoadedPackage<'bytes>,
+}
+
+impl<'a, 'bytes> PackageRef<'a, 'bytes> {
+    pub fn id(&self) -> u8 {
+        self.package.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.package.name
+    }
+
+    /// Shared library packages are compiled with package id 0x00; the real id is assigned at
+    /// runtime. Resource ids reported for such a package are placeholders: the caller must
+    /// replace the package byte with the id the library is loaded under.
+    pub fn is_dynamic_library(&self) -> bool {
+        self.package.is_dynamic_library
+    }
+
+    /// The shared libraries this package references, as (build time package id, package name).
+    pub fn libraries(&self) -> &[(u8, String)] {
+        &self.package.libraries
+    }
+}
+
+/// A resource together with its names, as yielded by `LoadedTable::entries`.
+pub struct ResourceEntry<'a, 'bytes> {
+    table: &'a LoadedTable<'bytes>,
+    package: &'a LoadedPackage<'bytes>,
+    type_name: &'a str,
+    entry: &'a LoadedEntry<'bytes>,
+    resid: ResourceId,
+}
+
+impl<'a, 'bytes> ResourceEntry<'a, 'bytes> {
+    pub fn id(&self) -> ResourceId {
+        self.resid
+    }
+
+    pub fn package_name(&self) -> &'a str {
+        &self.package.name
+    }
+
+    pub fn type_name(&self) -> &'a str {
+        self.type_name
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.entry.name
+    }
+
+    /// The configurations the resource has a value in, in the order the values are stored.
+    pub fn configurations(&self) -> Vec<ResourceConfiguration> {
+        self.entry.values.iter().map(|cv| cv.0.clone()).collect()
+    }
+
+    /// Same as `LoadedTable::value_for_resid`, without looking up the entry again.
+    pub fn values(&self) -> Option<Vec<(ResourceConfiguration, ResourceValue)>> {
+        self.table.entry_values(self.package.id, self.entry)
+    }
+
+    // like values, but with each value decoded on its own, so one bad value does not hide the
+    // others
+    pub(crate) fn each_value(&self) -> Vec<(ResourceConfiguration, Option<ResourceValue>)> {
+        self.entry
+            .values
+            .iter()
+            .map(|cv| {
+                let value = self.table.loaded_value_to_res_value(self.package.id, &cv.1);
+                (cv.0.clone(), value.ok())
+            })
+            .collect()
+    }
+
+    /// The flags of the entry, combined over all configurations it has a value in.
+    pub fn flags(&self) -> EntryFlags {
+        self.entry
+            .values
+            .iter()
+            .fold(EntryFlags::empty(), |flags, cv| flags | cv.1.flags())
+    }
+
+    /// The value in the default configuration, or None if the resource has no default value or
+    /// it cannot be decoded.
+    pub fn value(&self) -> Option<ResourceValue> {
+        let config_and_value = self.entry.values.iter().find(|cv| cv.0.is_default())?;
+        self.table
+            .loaded_value_to_res_value(self.package.id, &config_and_value.1)
+            .ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ParseOptions {
+    /// Fail on any inconsistency in the data, or on data this crate does not support (the
+    /// default). If false, problems that can be worked around are recorded as warnings instead,
+    /// and unsupported or unexpected chunks, and packages that fail to parse, are skipped; see
+    /// `LoadedTable::warnings`.
+    pub strict: bool,
+    /// Skip data this crate does not support, with a warning, even in strict mode. Corrupt or
+    /// unexpected data is still an error unless `strict` is false. Off by default.
+    pub skip_unsupported: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            strict: true,
+            skip_unsupported: false,
+        }
+    }
+}
+
+struct ParseContext {
+    options: ParseOptions,
+    warnings: Vec<String>,
+}
+
+impl ParseContext {
+    fn recover(&mut self, message: String) -> Result<(), Error> {
+        if self.options.strict {
+            Err(Error::CorruptData(message))
+        } else {
+            self.warnings.push(message);
+            Ok(())
+        }
+    }
+
+    // like recover, but for data that failed to parse: the offending part is skipped, or the
+    // error is returned as is in strict mode
+    fn skip(&mut self, what: &str, error: Error) -> Result<(), Error> {
+        let unsupported = matches!(error, Error::Unsupported(_));
+        if self.options.strict && !(unsupported && self.options.skip_unsupported) {
+            Err(error)
+        } else {
+            self.warnings.push(format!("skipping {}: {}", what, error));
+            Ok(())
+        }
+    }
+
+    // like recover, but for valid data this crate cannot handle yet: the offending part is
+    // skipped
+    fn unsupported(&mut self, what: String) -> Result<(), Error> {
+        if self.options.strict && !self.options.skip_unsupported {
+            Err(Error::Unsupported(what))
+        } else {
+            self.warnings.push(format!("skipping unsupported {}", what));
+            Ok(())
+        }
+    }
+}
+
 pub struct LoadedTable<'bytes> {
     _bytes: &'bytes [u8],
-    #[allow(dead_code)]
     value_strings: LoadedStringPool<'bytes>,
     packages: Vec<LoadedPackage<'bytes>>,
+    options: ParseOptions,
+    warnings: Vec<String>,
 }
 
 impl<'bytes> LoadedTable<'bytes> {
     pub fn parse(bytes: &'bytes [u8]) -> Result<LoadedTable<'bytes>, Error> {
-        let mut iter = ChunkIterator::new(bytes);
-        let chunk = match iter.next() {
-            Some(Chunk::Table(b)) => Chunk::Table(b),
+        LoadedTable::parse_with_options(bytes, ParseOptions::default())
+    }
+
+    pub fn parse_with_options(
+        bytes: &'bytes [u8],
+        options: ParseOptions,
+    ) -> Result<LoadedTable<'bytes>, Error> {
+        let table_bytes = match ChunkIterator::new(bytes).next() {
+            Some(Chunk::Table(b)) => b,
             Some(x) => return Err(Error::CorruptData(format!("not a table chunk: {:?}", x))),
             None => return Err(Error::CorruptData("no data to traverse".to_owned())),
         };
-        if iter.next().is_some() {
+        // some tools pad the file to an alignment boundary with zeros
+        if bytes[table_bytes.len()..].iter().any(|&b| b != 0) {
             return Err(Error::CorruptData("trailing data after table".to_owned()));
         }
-        let (value_strings, packages) = LoadedTable::parse_table(chunk)?;
+        let chunk = Chunk::Table(table_bytes);
+        let mut ctx = ParseContext {
+            options,
+            warnings: Vec::new(),
+        };
+        let (value_strings, packages) = LoadedTable::parse_table(chunk, &mut ctx)?;
 
         Ok(LoadedTable {
             _bytes: bytes,
             value_strings,
             packages,
+            options,
+            warnings: ctx.warnings,
         })
     }
 
+    /// Parse a table that takes ownership of the bytes; see `OwnedTable`.
+    pub fn parse_owned(bytes: Vec<u8>) -> Result<OwnedTable, Error> {
+        OwnedTable::parse(bytes)
+    }
+
+    /// Problems found and worked around while parsing in non-strict mode.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn parse_bytes<B>(bytes: &'bytes B) -> Result<LoadedTable<'bytes>, Error>
+    where
+        B: AsRef<[u8]> + ?Sized,
+    {
+        LoadedTable::parse(bytes.as_ref())
+    }
+
+    /// Change the name of a type. Only the in-memory view is updated: the type string pool in the
+    /// underlying bytes is left as is.
+    pub fn rename_type(&mut self, package_name: &str, old: &str, new: &str) -> Result<(), Error> {
+        let p = self
+            .packages
+            .iter_mut()
+            .find(|p| p.name == package_name)
+            .ok_or_else(|| Error::InvalidInput(format!("no package {}", package_name)))?;
+        if p.type_registry.id(new).is_some() {
+            return Err(Error::InvalidInput(format!(
+                "{}: type {} already exists",
+                package_name, new
+            )));
+        }
+        p.type_registry
+            .rename(old, new)
+            .ok_or_else(|| Error::InvalidInput(format!("{}: no type {}", package_name, old)))
+    }
+
+    pub fn packages(&self) -> impl Iterator<Item = PackageRef<'_, 'bytes>> {
+        self.packages.iter().map(|package| PackageRef { package })
+    }
+
     pub fn resid_iter(&self) -> ResourceIdIterator {
         ResourceIdIterator::new(&self)
     }
 
+    /// Every resource with its names, in the same order as `resid_iter`. Cheaper than calling
+    /// `name_for_resid` for each resource id.
+    pub fn entries(&self) -> impl Iterator<Item = ResourceEntry<'_, 'bytes>> {
+        self.packages.iter().flat_map(move |p| {
+            p.types.iter().flat_map(move |t| {
+                let type_name = p.type_registry.name(t.id).unwrap_or_default();
+                t.entries.iter().map(move |e| ResourceEntry {
+                    table: self,
+                    package: p,
+                    type_name,
+                    entry: e,
+                    resid: ResourceId::from_parts(p.id, t.id, e.id),
+                })
+            })
+        })
+    }
+
+    /// Like `resid_iter`, but limited to the resources of a single package. An unknown package
+    /// yields nothing.
+    pub fn resid_iter_for_package(&self, package_id: u8) -> ResourceIdIterator<'_> {
+        ResourceIdIterator::filtered(self, |p, _| p.id == package_id)
+    }
+
+    /// Like `resid_iter`, but limited to the resources of a single type, e.g. "drawable". An
+    /// unknown package or type yields nothing.
+    pub fn resid_iter_in_type(&self, package_id: u8, type_name: &str) -> ResourceIdIterator<'_> {
+        ResourceIdIterator::filtered(self, |p, t| {
+            p.id == package_id && p.type_registry.name(t.id) == Some(type_name)
+        })
+    }
+
+    /// Resource ids of entries that have alternative values along any of the given axes, e.g.
+    /// `ConfigurationFlags::LOCALE` for all translated resources.
+    pub fn resid_iter_varying(
+        &self,
+        axes: ConfigurationFlags,
+    ) -> impl Iterator<Item = ResourceId> + '_ {
+        self.resid_iter().filter(move |resid| {
+            matches!(self.spec_flags(resid), Some(flags)
+                if ConfigurationFlags::from_bits_truncate(flags).intersects(axes))
+        })
+    }
+
+    /// Whether the resource is part of its package's public API, or None if the resource's type
+    /// has no spec chunk.
+    pub fn is_public(&self, resid: &ResourceId) -> Option<bool> {
+        let flags = ConfigurationFlags::from_bits_truncate(self.spec_flags(resid)?);
+        Some(flags.contains(ConfigurationFlags::PUBLIC))
+    }
+
+    /// The configuration axes the resource has alternative values along, or None if the
+    /// resource's type has no spec chunk.
+    pub fn config_changes(&self, resid: &ResourceId) -> Option<ConfigurationFlags> {
+        let flags = ConfigurationFlags::from_bits_truncate(self.spec_flags(resid)?);
+        Some(flags - ConfigurationFlags::PUBLIC)
+    }
+
     pub fn resid_for_name(
         &self,
         package_name: &str,
@@ -81,157 +406,827 @@ impl<'bytes> LoadedTable<'bytes> {
         entry_name: &str,
     ) -> Option<ResourceId> {
         let p = self.packages.iter().find(|p| p.name == package_name)?;
-        let t = p.types.iter().find(|t| t.name == type_name)?;
+        let t = p.type_by_name(type_name)?;
         let e = t.entries.iter().find(|e| e.name == entry_name)?;
         Some(ResourceId::from_parts(p.id, t.id, e.id))
     }
 
+    /// True if the resource is of type "id". The table does not record whether an id was
+    /// declared with "@+id/" or only referenced, so callers formatting references can only guess.
+    pub fn is_id_type(&self, resid: &ResourceId) -> bool {
+        self.packages
+            .iter()
+            .find(|p| p.id == resid.package_id())
+            .and_then(|p| p.type_registry.name(resid.type_id()))
+            == Some("id")
+    }
+
+    /// Parse a resource id given either in hex ("0x7f020001") or by name
+    /// ("test.app:string/app_name", optionally prefixed by '@'). If the package is left out
+    /// ("string/app_name"), the packages are searched in order.
+    pub fn parse_resid(&self, s: &str) -> Result<ResourceId, Error> {
+        if s.starts_with("0x") {
+            return s.parse();
+        }
+        let name = s.strip_prefix('@').unwrap_or(s);
+        let (package_name, rest) = match name.split_once(':') {
+            Some((package_name, rest)) => (Some(package_name), rest),
+            None => (None, name),
+        };
+        let (type_name, entry_name) = rest.split_once('/').ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "bad resource {:?}: expected 0x followed by 8 hex digits or [package:]type/name",
+                s
+            ))
+        })?;
+        let resid = match package_name {
+            Some(package_name) => self.resid_for_name(package_name, type_name, entry_name),
+            None => self
+                .packages
+                .iter()
+                .find_map(|p| self.resid_for_name(&p.name, type_name, entry_name)),
+        };
+        resid.ok_or_else(|| Error::InvalidInput(format!("no resource named {}", name)))
+    }
+
+    /// The value of the resource a reference such as "@test.app:string/app_name" or
+    /// "?android:attr/colorPrimary" refers to, with references followed as by
+    /// `resolve_reference` in the default configuration. As for `parse_resid`, the package may
+    /// be left out; "android" refers to the framework package (id 0x01) even if it is named
+    /// differently. For "?" references this is the value of the attribute itself, as there is
+    /// no theme to resolve it against. Returns None if the reference cannot be resolved.
+    pub fn resolve_named_reference(&self, reference: &str) -> Option<ResourceValue> {
+        let name = reference
+            .strip_prefix('@')
+            .or_else(|| reference.strip_prefix('?'))?;
+        let resid = self.parse_resid(name).ok().or_else(|| {
+            let (type_name, entry_name) = name.strip_prefix("android:")?.split_once('/')?;
+            let framework = self.packages.iter().find(|p| p.id == 0x01)?;
+            self.resid_for_name(&framework.name, type_name, entry_name)
+        })?;
+        self.resolve_reference(&resid, &ResourceConfiguration::default())
+            .ok()?
+    }
+
     pub fn name_for_resid(&self, resid: &ResourceId) -> Option<(String, String, String)> {
         let p = self.packages.iter().find(|p| p.id == resid.package_id())?;
         let t = p.types.iter().find(|t| t.id == resid.type_id())?;
         let e = t.entries.iter().find(|e| e.id == resid.entry_id())?;
-        Some((p.name.clone(), t.name.clone(), e.name.clone()))
+        let type_name = p.type_registry.name(t.id)?;
+        Some((p.name.clone(), type_name.to_owned(), e.name.clone()))
+    }
+
+    pub fn package_names(&self) -> Vec<String> {
+        self.packages.iter().map(|p| p.name.clone()).collect()
+    }
+
+    /// The "package:type/name" of every resource, by resource id.
+    pub fn name_map(&self) -> BTreeMap<ResourceId, String> {
+        let mut map = BTreeMap::new();
+        for p in &self.packages {
+            for t in &p.types {
+                let type_name = p.type_registry.name(t.id).unwrap_or_default();
+                for e in &t.entries {
+                    map.insert(
+                        ResourceId::from_parts(p.id, t.id, e.id),
+                        format!("{}:{}/{}", p.name, type_name, e.name),
+                    );
+                }
+            }
+        }
+        map
+    }
+
+    pub fn type_names(&self, package_name: &str) -> Option<Vec<String>> {
+        let p = self.packages.iter().find(|p| p.name == package_name)?;
+        Some(
+            p.types
+                .iter()
+                .filter_map(|t| p.type_registry.name(t.id))
+                .map(|name| name.to_owned())
+                .collect(),
+        )
+    }
+
+    /// The id and name of each type of the package, sorted by id.
+    pub fn type_id_names(&self, package_name: &str) -> Option<Vec<(u8, String)>> {
+        let p = self.packages.iter().find(|p| p.name == package_name)?;
+        let mut v = p
+            .type_registry
+            .names
+            .iter()
+            .map(|(id, name)| (*id, name.clone()))
+            .collect::<Vec<_>>();
+        v.sort();
+        Some(v)
+    }
+
+    /// The number of entries of each type, by package name and type name.
+    pub fn type_matrix(&self) -> BTreeMap<String, BTreeMap<String, usize>> {
+        self.packages
+            .iter()
+            .map(|p| {
+                let types = p
+                    .types
+                    .iter()
+                    .filter_map(|t| Some((p.type_registry.name(t.id)?.to_owned(), t.entries.len())))
+                    .collect();
+                (p.name.clone(), types)
+            })
+            .collect()
+    }
+
+    pub fn entries_of_type(
+        &self,
+        package_name: &str,
+        type_name: &str,
+    ) -> Option<Vec<(ResourceId, String)>> {
+        let p = self.packages.iter().find(|p| p.name == package_name)?;
+        let t = p.type_by_name(type_name)?;
+        Some(
+            t.entries
+                .iter()
+                .map(|e| (ResourceId::from_parts(p.id, t.id, e.id), e.name.clone()))
+                .collect(),
+        )
+    }
+
+    /// The index'th entry of a type, in resource id order. Entry ids can have gaps, so the index
+    /// is not necessarily the entry id.
+    pub fn entry_at(
+        &self,
+        package_name: &str,
+        type_name: &str,
+        index: usize,
+    ) -> Option<(ResourceId, String)> {
+        let p = self.packages.iter().find(|p| p.name == package_name)?;
+        let t = p.type_by_name(type_name)?;
+        let e = t.entries.get(index)?;
+        Some((ResourceId::from_parts(p.id, t.id, e.id), e.name.clone()))
+    }
+
+    pub fn resids_of_type(&self, package_name: &str, type_name: &str) -> Option<Vec<ResourceId>> {
+        let p = self.packages.iter().find(|p| p.name == package_name)?;
+        let t = p.type_by_name(type_name)?;
+        Some(
+            t.entries
+                .iter()
+                .map(|e| ResourceId::from_parts(p.id, t.id, e.id))
+                .collect(),
+        )
+    }
+
+    /// The value of a resource in each of the configurations it is defined in, or None if the
+    /// resource does not exist or one of its values cannot be decoded.
+    pub fn value_for_resid(
+        &self,
+        resid: &ResourceId,
+    ) -> Option<Vec<(ResourceConfiguration, ResourceValue)>> {
+        self.entry_values(resid.package_id(), self.entry(resid)?)
     }
 
+    #[deprecated(note = "use value_for_resid")]
     pub fn lookup_all(
         &self,
         resid: &ResourceId,
     ) -> Option<Vec<(ResourceConfiguration, ResourceValue)>> {
-        let p = self.packages.iter().find(|p| p.id == resid.package_id())?;
-        let t = p.types.iter().find(|t| t.id == resid.type_id())?;
-        let e = t.entries.iter().find(|e| e.id == resid.entry_id())?;
+        self.value_for_resid(resid)
+    }
+
+    /// The value of the resource that a device with the given configuration would use, picked
+    /// the way Android picks it: out of the values whose configuration matches the device, the
+    /// one with the best configuration. None if the resource does not exist, has no matching
+    /// value, or the value cannot be decoded.
+    pub fn value_for_resid_config(
+        &self,
+        resid: &ResourceId,
+        config: &ResourceConfiguration,
+    ) -> Option<ResourceValue> {
+        let e = self.entry(resid)?;
+        let mut best: Option<&ConfigAndValue> = None;
+        for config_and_value in e.values.iter().filter(|cv| cv.0.matches(config)) {
+            let better = match best {
+                Some(b) => config_and_value.0.is_better_than(&b.0, config),
+                None => true,
+            };
+            if better {
+                best = Some(config_and_value);
+            }
+        }
+        self.loaded_value_to_res_value(resid.package_id(), &best?.1)
+            .ok()
+    }
+
+    /// The configurations the resource has a value in, in the order the values are stored, or
+    /// None if the resource does not exist.
+    pub fn configurations_for_resid(
+        &self,
+        resid: &ResourceId,
+    ) -> Option<Vec<ResourceConfiguration>> {
+        let e = self.entry(resid)?;
+        Some(e.values.iter().map(|cv| cv.0.clone()).collect())
+    }
+
+    /// The value of a string resource in each of the configurations it is defined in, with the
+    /// tag names of its style spans, e.g. "b" for a string declared as "<b>bold</b>". None if
+    /// the resource does not exist, or one of its values is not a string or cannot be decoded.
+    pub fn styled_string_for_resid(
+        &self,
+        resid: &ResourceId,
+    ) -> Option<Vec<(ResourceConfiguration, StyledString)>> {
+        let e = self.entry(resid)?;
+        let mut values = Vec::new();
+        for config_and_value in &e.values {
+            let value = match config_and_value.1 {
+                LoadedValue::Single(_, value) => value,
+                LoadedValue::Complex(..) => return None,
+            };
+            if value.type_.value() != ValueType::String as u8 {
+                return None;
+            }
+            let s = self
+                .value_strings
+                .styled_string_at(value.data.value() as usize)
+                .ok()?;
+            values.push((config_and_value.0.clone(), s));
+        }
+        Some(values)
+    }
+
+    /// The value of a complex resource (style, array, plurals, attr, ...) in each of the
+    /// configurations it is defined in, or None if the resource does not exist, is not complex,
+    /// or one of its values cannot be decoded.
+    pub fn complex_value_for_resid(
+        &self,
+        resid: &ResourceId,
+    ) -> Option<Vec<(ResourceConfiguration, ComplexValue)>> {
+        let e = self.entry(resid)?;
         let mut values = Vec::new();
         for config_and_value in &e.values {
             values.push((
-                self.chunk_config_to_res_config(&config_and_value.0),
-                self.loaded_value_to_res_value(&config_and_value.1).ok()?,
+                config_and_value.0.clone(),
+                self.loaded_value_to_complex_value(resid.package_id(), &config_and_value.1)
+                    .ok()?,
             ));
         }
         Some(values)
     }
 
-    fn chunk_config_to_res_config(&self, chunk: &Configuration) -> ResourceConfiguration {
-        ResourceConfiguration {
-            imsi: chunk.imsi.value(),
-            locale: chunk.locale.value(),
-            screen_type: chunk.screen_type.value(),
-            input: chunk.input.value(),
-            screen_size: chunk.screen_size.value(),
-            version: chunk.version.value(),
-            screen_config: chunk.screen_config.value(),
-            screen_size_dp: chunk.screen_size_dp.value(),
-        }
+    /// Every resource id with its decoded default configuration value, in resource id order.
+    /// Complex entries are yielded as `ResourceValue::Array`; resources without a default value,
+    /// or whose value cannot be decoded, are skipped.
+    pub fn iter_resolved(&self) -> impl Iterator<Item = (ResourceId, ResourceValue)> + '_ {
+        self.packages.iter().flat_map(move |p| {
+            p.types.iter().flat_map(move |t| {
+                t.entries.iter().filter_map(move |e| {
+                    let config_and_value = e.values.iter().find(|cv| cv.0.is_default())?;
+                    let value = self
+                        .loaded_value_to_res_value(p.id, &config_and_value.1)
+                        .ok()?;
+                    Some((ResourceId::from_parts(p.id, t.id, e.id), value))
+                })
+            })
+        })
     }
 
-    fn loaded_value_to_res_value(&self, value: &LoadedValue) -> Result<ResourceValue, Error> {
-        match value {
-            LoadedValue::Single(_, chunk) => self.chunk_value_to_res_value(&chunk),
-            LoadedValue::Complex(_, map) => {
-                let mut v = Vec::with_capacity(map.len());
-                for key_and_value in map.iter() {
-                    let key = key_and_value.key.value();
-                    let pp = ((key & 0xff00_0000) >> 24) as u8;
-                    let tt = ((key & 0x00ff_0000) >> 16) as u8 + 1; // type encoded as (n - 1)
-                    let eeee = (key & 0x0000_ffff) as u16;
-                    let resid = ResourceId::from_parts(pp, tt, eeee);
-
-                    let value = self.chunk_value_to_res_value(&key_and_value.value)?;
-
-                    v.push((resid, value));
+    /// The number of string resources with a value for each locale, keyed by BCP-47 tag; strings
+    /// with a value in a configuration without a locale are counted as "default".
+    pub fn locale_coverage(&self) -> BTreeMap<String, usize> {
+        let mut coverage = BTreeMap::new();
+        for p in &self.packages {
+            let t = match p.type_by_name("string") {
+                Some(t) => t,
+                None => continue,
+            };
+            for e in &t.entries {
+                let locales = e
+                    .values
+                    .iter()
+                    .map(|cv| cv.0.locale().unwrap_or_else(|| "default".to_owned()))
+                    .collect::<BTreeSet<_>>();
+                for locale in locales {
+                    *coverage.entry(locale).or_insert(0) += 1;
                 }
-                Ok(ResourceValue::Array(v))
             }
         }
+        coverage
     }
 
-    fn chunk_value_to_res_value(&self, chunk: &Value) -> Result<ResourceValue, Error> {
-        let type_: ValueType = chunk.type_.value().try_into().map_err(|_| {
-            Error::CorruptData(format!("bad chunk type {:#04x}", chunk.type_.value()))
-        })?;
-        let value = chunk.data.value();
-        match type_ {
-            ValueType::Null => Ok(ResourceValue::Null),
-            ValueType::Reference => Ok(ResourceValue::Reference(ResourceId::from_u32(value))),
-            ValueType::Attribute => Ok(ResourceValue::Attribute(ResourceId::from_u32(value))),
-            ValueType::String => {
-                let index = value as usize;
-                Ok(ResourceValue::String(self.value_strings.string_at(index)?))
-            }
-            ValueType::Float => Ok(ResourceValue::Float(f32::from_bits(value))),
-            ValueType::Dimension => {
-                // TODO(#11): correctly decode dimension value, see TypedValue.java
-                Ok(ResourceValue::Float(0_f32))
-            }
-            ValueType::Fraction => {
-                // TODO(#12): correctly decode fraction, see TypedValue.java
-                Ok(ResourceValue::Float(0_f32))
-            }
-            ValueType::DynamicReference | ValueType::DynamicAttribute => {
-                // the dynamic types are never encoded into the ARSC; they're only used to rewrite
-                // values during lookup
-                Err(Error::UnexpectedChunk)
-            }
-            ValueType::IntDec => Ok(ResourceValue::IntDec(value as i32)),
-            ValueType::IntHex => Ok(ResourceValue::IntHex(value as i32)),
-            ValueType::IntBoolean => Ok(ResourceValue::Boolean(value == 0xffff_ffff)),
-            ValueType::IntColorArgb8 => {
-                // TODO(#13): correctly decode color
-                Ok(ResourceValue::ColorArgb8(0_f32, 0_f32, 0_f32, 0_f32))
-            }
-            ValueType::IntColorRgb8 => {
-                // TODO(#13): correctly decode color
-                Ok(ResourceValue::ColorRgb8(0_f32, 0_f32, 0_f32))
-            }
-            ValueType::IntColorArgb4 => {
-                // TODO(#13): correctly decode color
-                Ok(ResourceValue::ColorArgb4(0_f32, 0_f32, 0_f32, 0_f32))
+    /// The default value of every resource, with references followed until a non-reference value
+    /// is found or `max_depth` references have been followed. References that cannot be resolved
+    /// are kept as is.
+    pub fn flatten(&self, max_depth: usize) -> BTreeMap<ResourceId, ResourceValue> {
+        self.iter_resolved()
+            .map(|(resid, mut value)| {
+                for _ in 0..max_depth {
+                    let next = match value {
+                        ResourceValue::Reference(target) => self.default_value(&target),
+                        _ => None,
+                    };
+                    match next {
+                        Some(next) => value = next,
+                        None => break,
+                    }
+                }
+                (resid, value)
+            })
+            .collect()
+    }
+
+    /// The value of a resource in the given configuration, or in the default configuration if
+    /// the resource has no value in that exact configuration, with references followed until a
+    /// non-reference value is found. References are followed through the default configuration
+    /// values of their targets. Returns None if the resource or one of the references does not
+    /// exist, and `Error::CorruptData` if the references form a cycle or a chain longer than 20.
+    pub fn resolve_reference(
+        &self,
+        resid: &ResourceId,
+        config: &ResourceConfiguration,
+    ) -> Result<Option<ResourceValue>, Error> {
+        const MAX_HOPS: usize = 20;
+
+        let e = match self.entry(resid) {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+        let config_and_value = match e
+            .values
+            .iter()
+            .find(|cv| cv.0 == *config)
+            .or_else(|| e.values.iter().find(|cv| cv.0.is_default()))
+        {
+            Some(config_and_value) => config_and_value,
+            None => return Ok(None),
+        };
+        let mut value = self.loaded_value_to_res_value(resid.package_id(), &config_and_value.1)?;
+        let mut seen = HashSet::new();
+        seen.insert(*resid);
+        while let ResourceValue::Reference(target) = value {
+            if !seen.insert(target) || seen.len() > MAX_HOPS {
+                return Err(Error::CorruptData(format!(
+                    "{}: reference loop at {}",
+                    resid, target
+                )));
             }
-            ValueType::IntColorRgb4 => {
-                // TODO(#13): correctly decode color
-                Ok(ResourceValue::ColorRgb4(0_f32, 0_f32, 0_f32))
+            value = match self.default_value(&target) {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+        }
+        Ok(Some(value))
+    }
+
+    pub fn types_without_spec(&self) -> Vec<(u8, String)> {
+        let mut v = Vec::new();
+        for p in &self.packages {
+            for t in p.types.iter().filter(|t| t.spec_flags.is_none()) {
+                let name = p.type_registry.name(t.id).unwrap_or_default();
+                v.push((t.id, name.to_owned()));
             }
         }
+        v
     }
 
-    fn parse_table(
-        chunk: Chunk<'bytes>,
-    ) -> Result<(LoadedStringPool, Vec<LoadedPackage<'bytes>>), Error> {
-        let details = chunk.as_table()?;
-        let mut packages = Vec::<LoadedPackage<'bytes>>::new();
-        let mut value_strings: Option<LoadedStringPool> = None;
+    /// The `n` longest strings in the value string pool as (index, length in bytes, string),
+    /// longest first. Strings that cannot be decoded are ignored.
+    pub fn largest_value_strings(&self, n: usize) -> Vec<(usize, usize, String)> {
+        let mut strings = self
+            .value_strings
+            .strings()
+            .enumerate()
+            .filter_map(|(i, s)| s.ok().map(|s| (i, s.len(), s)))
+            .collect::<Vec<_>>();
+        strings.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        strings.truncate(n);
+        strings
+    }
 
-        let iter = chunk
-            .iter()
-            .ok_or_else(|| Error::CorruptData("cannot iterate over table".to_owned()))?;
-        for child in iter {
-            match child {
-                Chunk::StringPool(_) => {
-                    if value_strings.is_some() {
-                        return Err(Error::CorruptData(
-                            "muiltiple string pools in table".to_owned(),
-                        ));
-                    }
-                    value_strings = Some(LoadedTable::parse_stringpool(child)?);
+    /// Strings which occur at more than one index in the value string pool, in order of first
+    /// occurrence. Strings that cannot be decoded are ignored.
+    pub fn duplicate_value_strings(&self) -> Vec<(String, Vec<usize>)> {
+        let mut order = Vec::new();
+        let mut indices: HashMap<String, Vec<usize>> = HashMap::new();
+        for i in 0..self.value_strings.string_count() {
+            if let Ok(s) = self.value_strings.string_at(i) {
+                let v = indices.entry(s.clone()).or_default();
+                if v.is_empty() {
+                    order.push(s);
                 }
-                Chunk::Package(_) => {
-                    packages.push(LoadedTable::parse_package(child)?);
+                v.push(i);
+            }
+        }
+        order
+            .into_iter()
+            .filter_map(|s| {
+                let v = indices.remove(&s)?;
+                if v.len() > 1 {
+                    Some((s, v))
+                } else {
+                    None
                 }
-                _ => return Err(Error::UnexpectedChunk),
+            })
+            .collect()
+    }
+
+    /// Package names which are used by more than one package id, with the ids in ascending
+    /// order. `@package:type/name` references to such names are ambiguous.
+    pub fn package_name_collisions(&self) -> Vec<(String, Vec<u8>)> {
+        let mut names: Vec<(String, Vec<u8>)> = Vec::new();
+        for p in &self.packages {
+            match names.iter_mut().find(|(name, _)| *name == p.name) {
+                Some((_, ids)) if !ids.contains(&p.id) => ids.push(p.id),
+                Some(_) => {}
+                None => names.push((p.name.clone(), vec![p.id])),
             }
         }
+        names
+            .into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(name, mut ids)| {
+                ids.sort_unstable();
+                (name, ids)
+            })
+            .collect()
+    }
 
-        if value_strings.is_none() {
-            return Err(Error::CorruptData(
-                "missing string pool in table".to_owned(),
+    /// The encoding (UTF-8 or not) and sorted flags of each string pool in the table.
+    pub fn string_pool_summary(&self) -> Vec<(PoolKind, bool, bool)> {
+        let mut v = vec![(
+            PoolKind::Value,
+            self.value_strings.is_utf8(),
+            self.value_strings.is_sorted(),
+        )];
+        for p in &self.packages {
+            v.push((
+                PoolKind::Type(p.id),
+                p.type_strings.is_utf8(),
+                p.type_strings.is_sorted(),
+            ));
+            v.push((
+                PoolKind::Key(p.id),
+                p.name_strings.is_utf8(),
+                p.name_strings.is_sorted(),
             ));
         }
+        v
+    }
 
-        if packages.len() != details.package_count.value() as usize {
-            return Err(Error::CorruptData(format!(
-                "expected {} packages, found {}",
+    /// Sanity check the loaded data. Returns a human readable description of each problem
+    /// found; an empty vector means the table is consistent.
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for p in &self.packages {
+            for t in &p.types {
+                for w in t.entries.windows(2).filter(|w| w[0].id >= w[1].id) {
+                    warnings.push(format!(
+                        "package {:#04x} type {:#04x}: entry id {:#06x} follows {:#06x}",
+                        p.id, t.id, w[1].id, w[0].id
+                    ));
+                }
+            }
+        }
+        warnings.extend(self.validate_string_pool_alignment());
+        warnings
+    }
+
+    /// Check that the string and style data of each string pool start at 4-byte aligned
+    /// offsets. Returns a description of each problem found.
+    pub fn validate_string_pool_alignment(&self) -> Vec<String> {
+        let mut pools = vec![(PoolKind::Value, &self.value_strings)];
+        for p in &self.packages {
+            pools.push((PoolKind::Type(p.id), &p.type_strings));
+            pools.push((PoolKind::Key(p.id), &p.name_strings));
+        }
+        pools
+            .into_iter()
+            .flat_map(|(kind, pool)| {
+                pool.misaligned_offsets()
+                    .into_iter()
+                    .map(move |problem| format!("{:?} string pool: {}", kind, problem))
+            })
+            .collect()
+    }
+
+    pub fn is_complex(&self, resid: &ResourceId) -> Option<bool> {
+        let e = self.entry(resid)?;
+        match e.values.iter().find(|cv| cv.0.is_default())?.1 {
+            LoadedValue::Single(..) => Some(false),
+            LoadedValue::Complex(..) => Some(true),
+        }
+    }
+
+    pub fn parent_of(&self, resid: &ResourceId) -> Option<ResourceId> {
+        let e = self.entry(resid)?;
+        match e.values.iter().find(|cv| cv.0.is_default())?.1 {
+            LoadedValue::Single(..) => None,
+            LoadedValue::Complex(map_entry, _) => match map_entry.parent_id.value() {
+                0 => None,
+                id => Some(ResourceId::from_u32(id)),
+            },
+        }
+    }
+
+    /// The name of the enum value `value` of the attribute `attr_resid`, e.g. "vertical" for
+    /// android:attr/orientation and 1. Returns None if the attribute is not an enum, or if no
+    /// enum value matches.
+    pub fn attr_enum_name(&self, attr_resid: &ResourceId, value: i32) -> Option<String> {
+        const ATTR_TYPE: u32 = 0x0100_0000; // ResTable_map::ATTR_TYPE
+        const ATTR_TYPE_ENUM: u32 = 1 << 16; // ResTable_map::TYPE_ENUM
+
+        let e = self.entry(attr_resid)?;
+        let map = match e.values.iter().find(|cv| cv.0.is_default())?.1 {
+            LoadedValue::Single(..) => return None,
+            LoadedValue::Complex(_, map) => map,
+        };
+        let type_ = map.iter().find(|kv| kv.key.value() == ATTR_TYPE)?;
+        if type_.value.data.value() & ATTR_TYPE_ENUM == 0 {
+            return None;
+        }
+        // the special ATTR_* keys all live in the 0x0100xxxx range; every other key is the id of
+        // an enum value
+        let kv = map.iter().find(|kv| {
+            kv.key.value() & 0xffff_0000 != ATTR_TYPE && kv.value.data.value() as i32 == value
+        })?;
+        let (_, _, name) = self.name_for_resid(&ResourceId::from_u32(kv.key.value()))?;
+        Some(name)
+    }
+
+    /// The resource followed by its parent, grandparent, etc. The walk stops at an entry without
+    /// a parent, at a parent not defined in this table, or when a cycle is detected.
+    pub fn style_ancestry(&self, resid: &ResourceId) -> Vec<ResourceId> {
+        let mut chain = Vec::new();
+        let mut seen = HashSet::new();
+        let mut current = Some(*resid);
+        while let Some(resid) = current {
+            if self.entry(&resid).is_none() || !seen.insert(resid) {
+                break;
+            }
+            current = self.parent_of(&resid);
+            chain.push(resid);
+        }
+        chain
+    }
+
+    /// References to resources that do not exist, as (source, missing target) pairs in resource
+    /// id order. Every value of every resource is checked, including the values of complex
+    /// entries. References to packages not part of this table (e.g. the framework when it is not
+    /// loaded) cannot be checked and are ignored, as are null references.
+    pub fn dangling_references(&self) -> Vec<(ResourceId, ResourceId)> {
+        let mut dangling = BTreeSet::new();
+        for p in &self.packages {
+            for t in &p.types {
+                for e in &t.entries {
+                    let source = ResourceId::from_parts(p.id, t.id, e.id);
+                    for config_and_value in &e.values {
+                        let value = match self.loaded_value_to_res_value(p.id, &config_and_value.1)
+                        {
+                            Ok(value) => value,
+                            Err(_) => continue,
+                        };
+                        let values = match value {
+                            ResourceValue::Array(entries) => {
+                                entries.into_iter().map(|(_, value)| value).collect()
+                            }
+                            value => vec![value],
+                        };
+                        for value in values {
+                            if let ResourceValue::Reference(target) = value {
+                                if target != 0
+                                    && self.packages.iter().any(|p| p.id == target.package_id())
+                                    && self.entry(&target).is_none()
+                                {
+                                    dangling.insert((source, target));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        dangling.into_iter().collect()
+    }
+
+    fn entry_values(
+        &self,
+        package_id: u8,
+        e: &LoadedEntry,
+    ) -> Option<Vec<(ResourceConfiguration, ResourceValue)>> {
+        let mut values = Vec::new();
+        for config_and_value in &e.values {
+            values.push((
+                config_and_value.0.clone(),
+                self.loaded_value_to_res_value(package_id, &config_and_value.1)
+                    .ok()?,
+            ));
+        }
+        Some(values)
+    }
+
+    fn default_value(&self, resid: &ResourceId) -> Option<ResourceValue> {
+        let e = self.entry(resid)?;
+        let config_and_value = e.values.iter().find(|cv| cv.0.is_default())?;
+        self.loaded_value_to_res_value(resid.package_id(), &config_and_value.1)
+            .ok()
+    }
+
+    fn spec_flags(&self, resid: &ResourceId) -> Option<u32> {
+        let p = self.packages.iter().find(|p| p.id == resid.package_id())?;
+        let t = p.types.iter().find(|t| t.id == resid.type_id())?;
+        t.spec_flags
+            .as_ref()?
+            .get(resid.entry_id() as usize)
+            .copied()
+    }
+
+    fn entry(&self, resid: &ResourceId) -> Option<&LoadedEntry<'bytes>> {
+        let p = self.packages.iter().find(|p| p.id == resid.package_id())?;
+        let t = p.types.iter().find(|t| t.id == resid.type_id())?;
+        t.entries.iter().find(|e| e.id == resid.entry_id())
+    }
+
+    // Rewrite the package byte of a dynamic reference made from the package with the given id:
+    // 0x00 refers to the package itself, other ids are looked up by name in the package's
+    // library table. References to libraries not part of this table are left unchanged.
+    fn resolve_dynamic_resid(&self, package_id: u8, resid: u32) -> ResourceId {
+        let resid = ResourceId::from_u32(resid);
+        let build_id = resid.package_id();
+        let runtime_id = match build_id {
+            0x00 => package_id,
+            0x01 | 0x7f => build_id,
+            _ => self
+                .packages
+                .iter()
+                .find(|p| p.id == package_id)
+                .and_then(|p| p.libraries.iter().find(|(id, _)| *id == build_id))
+                .and_then(|(_, name)| self.packages.iter().find(|p| &p.name == name))
+                .map_or(build_id, |p| p.id),
+        };
+        ResourceId::from_parts(runtime_id, resid.type_id(), resid.entry_id())
+    }
+
+    fn loaded_value_to_res_value(
+        &self,
+        package_id: u8,
+        value: &LoadedValue,
+    ) -> Result<ResourceValue, Error> {
+        match value {
+            LoadedValue::Single(_, chunk) => self.chunk_value_to_res_value(package_id, &chunk),
+            LoadedValue::Complex(..) => Ok(ResourceValue::Array(
+                self.loaded_value_to_complex_value(package_id, value)?
+                    .entries,
+            )),
+        }
+    }
+
+    fn loaded_value_to_complex_value(
+        &self,
+        package_id: u8,
+        value: &LoadedValue,
+    ) -> Result<ComplexValue, Error> {
+        let (map_entry, map) = match value {
+            LoadedValue::Single(..) => {
+                return Err(Error::InvalidInput("not a complex value".to_owned()))
+            }
+            LoadedValue::Complex(map_entry, map) => (map_entry, map),
+        };
+        let mut entries = Vec::with_capacity(map.len());
+        // in non-strict mode, children with a bad key or value are skipped
+        for key_and_value in map.iter() {
+            let key = key_and_value.key.value();
+            if key == 0 {
+                if self.options.strict {
+                    return Err(Error::CorruptData("complex entry with key 0".to_owned()));
+                }
+                continue;
+            }
+            let resid = ResourceId::from_u32(key);
+
+            let value = match self.chunk_value_to_res_value(package_id, &key_and_value.value) {
+                Ok(value) => value,
+                Err(e) if self.options.strict => return Err(e),
+                Err(_) => continue,
+            };
+
+            entries.push((resid, value));
+        }
+        let parent = match map_entry.parent_id.value() {
+            0 => None,
+            id => Some(ResourceId::from_u32(id)),
+        };
+        Ok(ComplexValue { parent, entries })
+    }
+
+    fn chunk_value_to_res_value(
+        &self,
+        package_id: u8,
+        chunk: &Value,
+    ) -> Result<ResourceValue, Error> {
+        let type_: ValueType = chunk.type_.value().try_into().map_err(|_| {
+            Error::CorruptData(format!("bad chunk type {:#04x}", chunk.type_.value()))
+        })?;
+        let value = chunk.data.value();
+        match type_ {
+            ValueType::Null => Ok(ResourceValue::Null),
+            ValueType::Reference => Ok(ResourceValue::Reference(ResourceId::from_u32(value))),
+            ValueType::Attribute => Ok(ResourceValue::Attribute(ResourceId::from_u32(value))),
+            ValueType::String => {
+                let index = value as usize;
+                let s = self.value_strings.string_at(index).map_err(|e| match e {
+                    Error::BadIndex => Error::CorruptData(format!(
+                        "string index {} out of range ({} strings)",
+                        index,
+                        self.value_strings.string_count()
+                    )),
+                    e => e,
+                })?;
+                Ok(ResourceValue::String(s))
+            }
+            ValueType::Float => Ok(ResourceValue::Float(f32::from_bits(value))),
+            ValueType::Dimension => {
+                let unit =
+                    DimensionUnit::try_from((value & COMPLEX_UNIT_MASK) as u8).map_err(|_| {
+                        Error::CorruptData(format!("bad dimension unit in {:#010x}", value))
+                    })?;
+                Ok(ResourceValue::Dimension(complex_to_float(value), unit))
+            }
+            ValueType::Fraction => {
+                let unit =
+                    FractionUnit::try_from((value & COMPLEX_UNIT_MASK) as u8).map_err(|_| {
+                        Error::CorruptData(format!("bad fraction unit in {:#010x}", value))
+                    })?;
+                Ok(ResourceValue::Fraction(complex_to_float(value), unit))
+            }
+            ValueType::DynamicReference => Ok(ResourceValue::Reference(
+                self.resolve_dynamic_resid(package_id, value),
+            )),
+            ValueType::DynamicAttribute => Ok(ResourceValue::Attribute(
+                self.resolve_dynamic_resid(package_id, value),
+            )),
+            ValueType::IntDec => Ok(ResourceValue::IntDec(value as i32)),
+            ValueType::IntHex => Ok(ResourceValue::IntHex(value as i32)),
+            ValueType::IntBoolean => Ok(ResourceValue::Boolean(value == 0xffff_ffff)),
+            ValueType::IntColorArgb8 => {
+                let [a, r, g, b] = color_components(value);
+                Ok(ResourceValue::ColorArgb8(a, r, g, b))
+            }
+            ValueType::IntColorRgb8 => {
+                let [_, r, g, b] = color_components(value);
+                Ok(ResourceValue::ColorRgb8(r, g, b))
+            }
+            ValueType::IntColorArgb4 => {
+                let [a, r, g, b] = color_components(value);
+                Ok(ResourceValue::ColorArgb4(a, r, g, b))
+            }
+            ValueType::IntColorRgb4 => {
+                let [_, r, g, b] = color_components(value);
+                Ok(ResourceValue::ColorRgb4(r, g, b))
+            }
+        }
+    }
+
+    fn parse_table(
+        chunk: Chunk<'bytes>,
+        ctx: &mut ParseContext,
+    ) -> Result<(LoadedStringPool<'bytes>, Vec<LoadedPackage<'bytes>>), Error> {
+        let details = chunk.as_table()?;
+        let mut packages = Vec::<LoadedPackage<'bytes>>::new();
+        let mut value_strings: Option<LoadedStringPool> = None;
+
+        let iter = chunk
+            .iter()
+            .ok_or_else(|| Error::CorruptData("cannot iterate over table".to_owned()))?;
+        for child in iter {
+            match child {
+                Chunk::StringPool(_) => {
+                    if value_strings.is_some() {
+                        return Err(Error::CorruptData(
+                            "muiltiple string pools in table".to_owned(),
+                        ));
+                    }
+                    value_strings = Some(LoadedTable::parse_stringpool(child)?);
+                }
+                Chunk::Package(_) => match LoadedTable::parse_package(child, ctx) {
+                    Ok(package) => packages.push(package),
+                    Err(e) => ctx.skip("package", e)?,
+                },
+                Chunk::Null(_) => {}
+                Chunk::Error(msg) => ctx.skip("rest of table", Error::CorruptData(msg))?,
+                _ => ctx.skip(&chunk_description(&child), Error::UnexpectedChunk)?,
+            }
+        }
+
+        if value_strings.is_none() {
+            return Err(Error::CorruptData(
+                "missing string pool in table".to_owned(),
+            ));
+        }
+
+        if packages.len() != details.package_count.value() as usize {
+            ctx.recover(format!(
+                "expected {} packages, found {}",
                 details.package_count.value(),
                 packages.len()
-            )));
+            ))?;
         }
 
         Ok((value_strings.unwrap(), packages))
@@ -241,11 +1236,16 @@ impl<'bytes> LoadedTable<'bytes> {
         LoadedStringPool::from_chunk(chunk)
     }
 
-    fn parse_package(chunk: Chunk<'bytes>) -> Result<LoadedPackage<'bytes>, Error> {
+    fn parse_package(
+        chunk: Chunk<'bytes>,
+        ctx: &mut ParseContext,
+    ) -> Result<LoadedPackage<'bytes>, Error> {
         let details = chunk.as_package()?;
         let mut type_strings: Option<LoadedStringPool> = None;
         let mut name_strings: Option<LoadedStringPool> = None;
         let mut types: HashMap<u8, Vec<Vec<Option<ConfigAndValue<'bytes>>>>> = HashMap::new();
+        let mut spec_flags = HashMap::new();
+        let mut libraries = Vec::new();
 
         let iter = chunk
             .iter()
@@ -255,8 +1255,8 @@ impl<'bytes> LoadedTable<'bytes> {
                 Chunk::StringPool(_bytes) => {
                     let child_details = child.as_stringpool()?;
 
-                    let base_addr: usize = unsafe { mem::transmute(details) };
-                    let child_addr: usize = unsafe { mem::transmute(child_details) };
+                    let base_addr = details as *const Package as usize;
+                    let child_addr = child_details as *const StringPool as usize;
                     let offset = child_addr - base_addr;
 
                     if offset == details.types_string_buffer_offset.value() as usize {
@@ -274,21 +1274,44 @@ impl<'bytes> LoadedTable<'bytes> {
                         }
                         name_strings = Some(LoadedTable::parse_stringpool(child)?);
                     } else {
-                        return Err(Error::CorruptData(
-                            "unexpected string pool in package".to_owned(),
-                        ));
+                        // stale offsets: fall back to the order aapt writes the pools in
+                        ctx.recover(format!(
+                            "unexpected string pool in package at offset {:#x}",
+                            offset
+                        ))?;
+                        if type_strings.is_none() {
+                            type_strings = Some(LoadedTable::parse_stringpool(child)?);
+                        } else if name_strings.is_none() {
+                            name_strings = Some(LoadedTable::parse_stringpool(child)?);
+                        } else {
+                            return Err(Error::CorruptData(
+                                "unexpected string pool in package".to_owned(),
+                            ));
+                        }
                     }
                 }
                 Chunk::Spec(_bytes) => {
-                    LoadedTable::parse_spec(child)?;
+                    let (spec, flags) = LoadedTable::parse_spec(child)?;
+                    spec_flags.insert(spec.id.value(), flags);
                 }
                 Chunk::Type(_bytes) => {
-                    let tt = child.as_type().unwrap().id.value() as u8;
-                    let values = LoadedTable::parse_type(child)?;
+                    let tt = child.as_type()?.id.value();
+                    let values = match LoadedTable::parse_type(child) {
+                        Err(Error::Unsupported(what)) => {
+                            ctx.unsupported(format!("type {:#04x}: {}", tt, what))?;
+                            continue;
+                        }
+                        result => result?,
+                    };
                     types.entry(tt).or_default();
                     types.entry(tt).and_modify(|e| e.push(values));
                 }
-                _ => return Err(Error::UnexpectedChunk),
+                Chunk::Library(_bytes) => {
+                    libraries.extend(LoadedTable::parse_library(child)?);
+                }
+                Chunk::Null(_) => {}
+                Chunk::Error(msg) => ctx.skip("rest of package", Error::CorruptData(msg))?,
+                _ => ctx.skip(&chunk_description(&child), Error::UnexpectedChunk)?,
             }
         }
 
@@ -310,14 +1333,25 @@ impl<'bytes> LoadedTable<'bytes> {
             }
         };
 
-        let name = LittleEndianU16::decode_string(&details.name);
+        let name = LittleEndianU16::decode_string(&details.name)?;
 
+        let mut type_registry = TypeRegistry::default();
         let mut loaded_types = Vec::new();
         let mut sorted_ids = types.keys().copied().collect::<Vec<_>>();
         sorted_ids.sort_unstable();
         for id in sorted_ids {
             let all_values = types.get(&id).unwrap();
-            let size = all_values.first().unwrap().len();
+            if let Some(flags) = spec_flags.get(&id) {
+                for values in all_values.iter().filter(|v| v.len() != flags.len()) {
+                    ctx.recover(format!(
+                        "type {:#04x}: spec has {} entries, type has {}",
+                        id,
+                        flags.len(),
+                        values.len()
+                    ))?;
+                }
+            }
+            let size = all_values.iter().map(|v| v.len()).max().unwrap();
             let mut config_and_values: Vec<Vec<ConfigAndValue<'bytes>>> = Vec::new();
             config_and_values.resize_with(size, Vec::new);
             for values in all_values {
@@ -335,14 +1369,19 @@ impl<'bytes> LoadedTable<'bytes> {
                 if values.is_empty() {
                     continue;
                 }
-                let name = match values.first().unwrap().1 {
-                    LoadedValue::Single(entry, _) => name_strings
-                        .string_at(entry.key_index.value() as usize)
-                        .unwrap(),
-                    LoadedValue::Complex(map_entry, _) => name_strings
-                        .string_at(map_entry.entry.key_index.value() as usize)
-                        .unwrap(),
-                };
+                let key_index = match values.first().unwrap().1 {
+                    LoadedValue::Single(entry, _) => entry.key_index.value(),
+                    LoadedValue::Complex(map_entry, _) => map_entry.entry.key_index.value(),
+                } as usize;
+                let name = name_strings.string_at(key_index).map_err(|e| match e {
+                    Error::BadIndex => Error::CorruptData(format!(
+                        "type {:#04x}: key index {} out of range ({} strings)",
+                        id,
+                        key_index,
+                        name_strings.string_count()
+                    )),
+                    e => e,
+                })?;
                 entries.push(LoadedEntry {
                     id: config_and_values.len() as u16,
                     name,
@@ -350,81 +1389,156 @@ impl<'bytes> LoadedTable<'bytes> {
                 });
             }
             entries.sort_unstable_by_key(|entry| entry.id);
+            debug_assert!(entries.windows(2).all(|w| w[0].id < w[1].id));
 
-            debug_assert!(id > 0);
+            let type_index = id
+                .checked_sub(1)
+                .ok_or_else(|| Error::CorruptData("type id 0".to_owned()))?;
+            type_registry.insert(id, type_strings.string_at(type_index as usize)?);
             loaded_types.push(LoadedType {
                 id,
-                name: type_strings.string_at((id - 1) as usize)?,
+                spec_flags: spec_flags.remove(&id),
                 entries,
             });
         }
 
+        let id = details.id.value() as u8;
         Ok(LoadedPackage {
-            id: details.id.value() as u8,
+            id,
             name,
+            is_dynamic_library: id == 0,
+            libraries,
             type_strings,
             name_strings,
+            type_registry,
             types: loaded_types,
         })
     }
 
-    fn parse_spec(chunk: Chunk<'bytes>) -> Result<&'bytes Spec, Error> {
+    fn parse_spec(chunk: Chunk<'bytes>) -> Result<(&'bytes Spec, Vec<u32>), Error> {
         let details = chunk.as_spec()?;
-        Ok(details)
+        let bytes = match chunk {
+            Chunk::Spec(bytes) => bytes,
+            _ => return Err(Error::UnexpectedChunk),
+        };
+        let header_size = details.header.header_size.value() as usize;
+        let flags = (0..details.entry_count.value() as usize)
+            .map(|i| {
+                LittleEndianU32::read_at(bytes, header_size + i * 4)
+                    .ok_or_else(|| Error::CorruptData(format!("spec flags {} out of bounds", i)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((details, flags))
+    }
+
+    fn parse_library(chunk: Chunk<'bytes>) -> Result<Vec<(u8, String)>, Error> {
+        let details = chunk.as_library()?;
+        let bytes = match chunk {
+            Chunk::Library(bytes) => bytes,
+            _ => return Err(Error::UnexpectedChunk),
+        };
+        let header_size = details.header.header_size.value() as usize;
+        let mut libraries = Vec::new();
+        for i in 0..details.count.value() as usize {
+            let offset = header_size + i * mem::size_of::<LibraryEntry>();
+            let entry: &LibraryEntry = view(bytes, offset)?;
+            libraries.push((
+                entry.package_id.value() as u8,
+                LittleEndianU16::decode_string(&entry.package_name)?,
+            ));
+        }
+        Ok(libraries)
     }
 
     fn parse_type(chunk: Chunk<'bytes>) -> Result<Vec<Option<ConfigAndValue<'bytes>>>, Error> {
         let mut values = Vec::new();
         let details = chunk.as_type()?;
         if details.flags.value() & 0x01 != 0 {
-            unimplemented!("FLAG_SPARSE not supported yet");
+            return Err(Error::Unsupported("sparse type chunk".to_owned()));
         }
-        let config = &details.config;
-
-        let addr: usize = unsafe { mem::transmute(details) };
-        let addr = addr + details.header.header_size.value() as usize;
-        let payload = unsafe {
-            slice::from_raw_parts(
-                addr as *const LittleEndianU32,
-                details.entry_count.value() as usize,
-            )
+        let bytes = match chunk {
+            Chunk::Type(bytes) => bytes,
+            _ => return Err(Error::UnexpectedChunk),
         };
-        for offset in payload.iter() {
-            if offset.value() == 0xffff_ffff {
+
+        // legacy files store shorter configurations: read what is there and zero-fill the rest
+        let config_offset = mem::size_of::<Type>();
+        let config_size = LittleEndianU32::read_at(bytes, config_offset)
+            .ok_or_else(|| Error::CorruptData("configuration out of bounds".to_owned()))?
+            as usize;
+        let mut config = [0; mem::size_of::<Configuration>()];
+        let n = config_size.min(config.len());
+        config[..n].copy_from_slice(
+            bytes
+                .get(config_offset..config_offset + n)
+                .ok_or_else(|| Error::CorruptData("configuration out of bounds".to_owned()))?,
+        );
+        let config = ResourceConfiguration::from(view::<Configuration>(&config, 0)?);
+        let header_size = details.header.header_size.value() as usize;
+        let entries_offset = details.entries_offset.value() as usize;
+        for i in 0..details.entry_count.value() as usize {
+            let offset = LittleEndianU32::read_at(bytes, header_size + i * 4)
+                .ok_or_else(|| Error::CorruptData(format!("entry offset {} out of bounds", i)))?;
+            if offset == 0xffff_ffff {
                 values.push(None);
+                continue;
+            }
+
+            let offset = entries_offset + offset as usize;
+            let entry: &Entry = view(bytes, offset)?;
+
+            if entry.flags().contains(EntryFlags::COMPACT) {
+                return Err(Error::Unsupported("compact entry".to_owned()));
+            }
+            if !entry.flags().contains(EntryFlags::COMPLEX) {
+                let value: &Value = view(bytes, offset + entry.size.value() as usize)?;
+                values.push(Some(ConfigAndValue(
+                    config.clone(),
+                    LoadedValue::Single(entry, value),
+                )));
             } else {
-                let addr: usize = unsafe { mem::transmute(details) };
-                let addr = addr + details.entries_offset.value() as usize;
-                let addr = addr + offset.value() as usize;
-                let entry: &Entry = unsafe { mem::transmute(addr) };
-
-                if entry.flags.value() & 0x01 == 0 {
-                    let addr = addr + entry.size.value() as usize;
-                    let value: &Value = unsafe { mem::transmute(addr) };
-                    values.push(Some(ConfigAndValue(
-                        config,
-                        LoadedValue::Single(entry, value),
-                    )));
-                } else {
-                    let entry: &MapEntry = unsafe { mem::transmute(addr) };
-                    let addr = addr + entry.entry.size.value() as usize;
-                    let map: &[KeyAndValue] = unsafe {
-                        slice::from_raw_parts(
-                            addr as *const KeyAndValue,
-                            entry.count.value() as usize,
-                        )
-                    };
-                    values.push(Some(ConfigAndValue(
-                        config,
-                        LoadedValue::Complex(entry, map),
-                    )));
-                }
+                let entry: &MapEntry = view(bytes, offset)?;
+                let map: &[KeyAndValue] = view_slice(
+                    bytes,
+                    offset + entry.entry.size.value() as usize,
+                    entry.count.value() as usize,
+                )?;
+                values.push(Some(ConfigAndValue(
+                    config.clone(),
+                    LoadedValue::Complex(entry, map),
+                )));
             }
         }
         Ok(values)
     }
 }
 
+fn chunk_description(chunk: &Chunk) -> String {
+    match chunk.header() {
+        Some(header) => format!("chunk of type {:#06x}", header.type_.value()),
+        None => "chunk".to_owned(),
+    }
+}
+
+const COMPLEX_UNIT_MASK: u32 = 0xf;
+
+// The value of a dimension or fraction: a signed 24 bit mantissa in the high bits, and in bits 4
+// and 5 the position of the binary point in it (after bit 23, 16, 8 or 0). See TypedValue.java.
+fn complex_to_float(data: u32) -> f32 {
+    const RADIX_SHIFTS: [i32; 4] = [0, 7, 15, 23];
+    let mantissa = (data & 0xffff_ff00) as i32 >> 8;
+    let shift = RADIX_SHIFTS[((data >> 4) & 0x3) as usize];
+    mantissa as f32 / (1 << shift) as f32
+}
+
+// All color types are stored as 0xAARRGGBB, with 4 bit channels expanded to 8 bits (0xf -> 0xff)
+// and the alpha of the RGB types set to 0xff; the channels are straight, not premultiplied by
+// alpha. Returns the channels in [0, 1], in the order A, R, G, B.
+fn color_components(data: u32) -> [f32; 4] {
+    let channel = |shift: u32| ((data >> shift) & 0xff) as f32 / 255.0;
+    [channel(24), channel(16), channel(8), channel(0)]
+}
+
 pub struct ResourceIdIterator<'a> {
     iters: Vec<LoadedEntryIterator<'a>>,
     current: Option<LoadedEntryIterator<'a>>,
@@ -432,9 +1546,17 @@ pub struct ResourceIdIterator<'a> {
 
 impl<'a> ResourceIdIterator<'a> {
     pub fn new(table: &'a LoadedTable) -> ResourceIdIterator<'a> {
+        ResourceIdIterator::filtered(table, |_, _| true)
+    }
+
+    // iterate over the types for which the predicate, given the package and the type, is true
+    fn filtered<F>(table: &'a LoadedTable, predicate: F) -> ResourceIdIterator<'a>
+    where
+        F: Fn(&LoadedPackage, &LoadedType) -> bool,
+    {
         let mut iters = Vec::new();
         for pkg in &table.packages {
-            for type_ in &pkg.types {
+            for type_ in pkg.types.iter().filter(|t| predicate(pkg, t)) {
                 iters.push(LoadedEntryIterator {
                     package_id: pkg.id,
                     type_id: type_.id,
@@ -491,12 +1613,26 @@ impl<'a> Iterator for LoadedEntryIterator<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{LoadedPackage, LoadedTable};
+    use super::{LoadedPackage, LoadedTable, ParseOptions, PoolKind};
+    use crate::chunks::{Chunk, ConfigurationFlags, EntryFlags};
+    use crate::error::Error;
+    use crate::resources::{
+        ComplexValue, DimensionUnit, FractionUnit, ResourceConfiguration, ResourceValue,
+    };
+    use crate::stringpool::StyledString;
+    use crate::test_utils;
     use crate::ResourceId;
-    use std::collections::HashSet;
+    use std::collections::{BTreeMap, HashSet};
 
     const RESOURCE_ARSC: &[u8] = include_bytes!("../../tests/data/unpacked/resources.arsc");
 
+    fn lenient() -> ParseOptions {
+        ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        }
+    }
+
     #[test]
     fn parse_valid_table() {
         let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
@@ -531,6 +1667,35 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn parse_bytes() {
+        let bytes = RESOURCE_ARSC.to_vec();
+        let table = LoadedTable::parse_bytes(&bytes).unwrap();
+        assert_eq!(table.packages.len(), 1);
+
+        let table = LoadedTable::parse_bytes(RESOURCE_ARSC).unwrap();
+        assert_eq!(table.packages.len(), 1);
+    }
+
+    #[test]
+    fn parse_misaligned_bytes() {
+        let mut bytes = vec![0u8; RESOURCE_ARSC.len() + 1];
+        bytes[1..].copy_from_slice(RESOURCE_ARSC);
+        let table = LoadedTable::parse(&bytes[1..]).unwrap();
+        assert_eq!(
+            table.name_for_resid(&ResourceId::from_u32(0x7f020000)),
+            Some((
+                "test.app".to_owned(),
+                "string".to_owned(),
+                "app_name".to_owned()
+            ))
+        );
+        let values = table
+            .value_for_resid(&ResourceId::from_u32(0x7f020001))
+            .unwrap();
+        assert_eq!(values.len(), 4);
+    }
+
     #[test]
     fn resid_iter() {
         let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
@@ -633,19 +1798,1851 @@ mod tests {
     }
 
     #[test]
-    fn name_for_resid() {
+    fn package_names() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert_eq!(table.package_names(), vec!["test.app".to_owned()]);
+    }
+
+    #[test]
+    fn parse_resid() {
         let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let app_name = ResourceId::from_u32(0x7f020000);
+        assert_eq!(table.parse_resid("0x7f020000").unwrap(), app_name);
         assert_eq!(
-            table.name_for_resid(&ResourceId::from_u32(0x7f010000)),
-            Some(("test.app".to_owned(), "bool".to_owned(), "foo".to_owned()))
+            table.parse_resid("test.app:string/app_name").unwrap(),
+            app_name
         );
+        assert_eq!(
+            table.parse_resid("@test.app:string/app_name").unwrap(),
+            app_name
+        );
+        assert_eq!(table.parse_resid("string/app_name").unwrap(), app_name);
+
+        for s in &[
+            "0x7f02",
+            "test.app:string",
+            "app_name",
+            "other.app:string/app_name",
+            "test.app:string/does_not_exist",
+        ] {
+            match table.parse_resid(s) {
+                Err(Error::InvalidInput(_)) => {}
+                _ => panic!("expected Error::InvalidInput for {:?}", s),
+            }
+        }
+    }
+
+    #[test]
+    fn is_id_type() {
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["id", "integer"],
+                &["foo"],
+                &[
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::simple_entry(
+                            0,
+                            test_utils::VALUE_INT_DEC,
+                            0,
+                        ))],
+                    ),
+                    test_utils::type_(
+                        2,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::simple_entry(
+                            0,
+                            test_utils::VALUE_INT_DEC,
+                            0,
+                        ))],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert!(table.is_id_type(&ResourceId::from_u32(0x7f01_0000)));
+        assert!(!table.is_id_type(&ResourceId::from_u32(0x7f02_0000)));
+        assert!(!table.is_id_type(&ResourceId::from_u32(0x7f03_0000)));
+        assert!(!table.is_id_type(&ResourceId::from_u32(0x0101_0000)));
+    }
+
+    #[test]
+    fn spec_flags() {
+        let int = |key| Some(test_utils::simple_entry(key, test_utils::VALUE_INT_DEC, 0));
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["integer", "bool"],
+                &["foo", "bar"],
+                &[
+                    test_utils::spec(1, &[0x4000_0000, 0x0000_0104]),
+                    test_utils::type_(1, &test_utils::default_config(), &[int(0), int(1)]),
+                    test_utils::type_(2, &test_utils::default_config(), &[int(0)]),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let foo = ResourceId::from_u32(0x7f01_0000);
+        let bar = ResourceId::from_u32(0x7f01_0001);
+        assert_eq!(table.is_public(&foo), Some(true));
+        assert_eq!(
+            table.config_changes(&foo),
+            Some(ConfigurationFlags::empty())
+        );
+        assert_eq!(table.is_public(&bar), Some(false));
+        assert_eq!(
+            table.config_changes(&bar),
+            Some(ConfigurationFlags::LOCALE | ConfigurationFlags::DENSITY)
+        );
+
+        // no spec chunk for type 0x02
+        let resid = ResourceId::from_u32(0x7f02_0000);
+        assert_eq!(table.is_public(&resid), None);
+        assert_eq!(table.config_changes(&resid), None);
     }
 
     #[test]
-    fn lookup_all() {
+    fn entry_at() {
         let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
-        let resid = ResourceId::from_u32(0x7f020001); // string/foo
-        let values = table.lookup_all(&resid).expect("lookup succeeded");
-        assert_eq!(values.len(), 4); // -, sv, en-rXA, ar-rXB
+        assert_eq!(
+            table.entry_at("test.app", "string", 0),
+            Some((ResourceId::from_u32(0x7f02_0000), "app_name".to_owned()))
+        );
+        assert_eq!(
+            table.entry_at("test.app", "string", 1),
+            Some((ResourceId::from_u32(0x7f02_0001), "foo".to_owned()))
+        );
+        assert_eq!(table.entry_at("test.app", "string", 2), None);
+        assert_eq!(table.entry_at("test.app", "color", 0), None);
+        assert_eq!(table.entry_at("other.app", "string", 0), None);
+    }
+
+    #[test]
+    fn configurations_for_resid() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let qualifiers = |resid| {
+            table
+                .configurations_for_resid(&ResourceId::from_u32(resid))
+                .map(|configs| {
+                    configs
+                        .iter()
+                        .map(|config| config.to_qualifier())
+                        .collect::<Vec<_>>()
+                })
+        };
+        assert_eq!(
+            qualifiers(0x7f02_0000),
+            Some(vec![
+                "".to_owned(),
+                "en-rXA".to_owned(),
+                "ar-rXB".to_owned()
+            ])
+        );
+        assert_eq!(qualifiers(0x7f01_0000), Some(vec!["".to_owned()]));
+        assert_eq!(qualifiers(0x7f02_0002), None);
+    }
+
+    #[test]
+    fn complex_value_for_resid() {
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["style", "array", "integer"],
+                &["Theme", "numbers", "one"],
+                &[
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::complex_entry(
+                            0,
+                            0x0101_0000, // android:style/Theme
+                            &[(0x0101_0001, test_utils::VALUE_INT_DEC, 7)],
+                        ))],
+                    ),
+                    test_utils::type_(
+                        2,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::complex_entry(
+                            1,
+                            0,
+                            &[
+                                (0x0200_0000, test_utils::VALUE_INT_DEC, 1),
+                                (0x0200_0001, test_utils::VALUE_INT_DEC, 2),
+                            ],
+                        ))],
+                    ),
+                    test_utils::type_(
+                        3,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::simple_entry(
+                            2,
+                            test_utils::VALUE_INT_DEC,
+                            1,
+                        ))],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+
+        let values = table
+            .complex_value_for_resid(&ResourceId::from_u32(0x7f01_0000))
+            .unwrap();
+        assert_eq!(values.len(), 1);
+        assert!(values[0].0.is_default());
+        assert_eq!(
+            values[0].1,
+            ComplexValue {
+                parent: Some(ResourceId::from_u32(0x0101_0000)),
+                entries: vec![(ResourceId::from_u32(0x0101_0001), ResourceValue::IntDec(7))],
+            }
+        );
+
+        let values = table
+            .complex_value_for_resid(&ResourceId::from_u32(0x7f02_0000))
+            .unwrap();
+        assert_eq!(values[0].1.parent, None);
+        assert_eq!(
+            values[0].1.entries,
+            vec![
+                (ResourceId::from_u32(0x0200_0000), ResourceValue::IntDec(1)),
+                (ResourceId::from_u32(0x0200_0001), ResourceValue::IntDec(2)),
+            ]
+        );
+
+        assert!(table
+            .complex_value_for_resid(&ResourceId::from_u32(0x7f03_0000))
+            .is_none());
+        assert!(table
+            .complex_value_for_resid(&ResourceId::from_u32(0x7f04_0000))
+            .is_none());
+    }
+
+    #[test]
+    fn type_matrix() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let mut types = BTreeMap::new();
+        types.insert("bool".to_owned(), 1);
+        types.insert("string".to_owned(), 2);
+        let mut expected = BTreeMap::new();
+        expected.insert("test.app".to_owned(), types);
+        assert_eq!(table.type_matrix(), expected);
+    }
+
+    #[test]
+    fn name_map() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let actual = table
+            .name_map()
+            .into_iter()
+            .map(|(resid, name)| (u32::from(resid), name))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                (0x7f010000, "test.app:bool/foo".to_owned()),
+                (0x7f020000, "test.app:string/app_name".to_owned()),
+                (0x7f020001, "test.app:string/foo".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn type_names() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert_eq!(
+            table.type_names("test.app"),
+            Some(vec!["bool".to_owned(), "string".to_owned()])
+        );
+        assert!(table.type_names("-").is_none());
+    }
+
+    #[test]
+    fn value_for_resid_config() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let value = |resid, qualifier| {
+            let config = ResourceConfiguration::parse_qualifier(qualifier).unwrap();
+            table.value_for_resid_config(&ResourceId::from_u32(resid), &config)
+        };
+        let string = |s: &str| Some(ResourceValue::String(s.to_owned()));
+        assert_eq!(value(0x7f02_0001, ""), string("Foo"));
+        assert_eq!(value(0x7f02_0001, "sv"), string("Bar"));
+        assert_eq!(value(0x7f02_0001, "sv-rSE-xxhdpi-v30"), string("Bar"));
+        assert_eq!(value(0x7f02_0001, "de-rDE"), string("Foo"));
+        assert_eq!(value(0x7f01_0000, "sv"), Some(ResourceValue::Boolean(true)));
+        assert_eq!(value(0x7f02_ffff, "sv"), None);
+    }
+
+    #[test]
+    fn resolve_named_reference() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert_eq!(
+            table.resolve_named_reference("@test.app:string/app_name"),
+            Some(ResourceValue::String("Test app".to_owned()))
+        );
+        assert_eq!(
+            table.resolve_named_reference("@string/app_name"),
+            Some(ResourceValue::String("Test app".to_owned()))
+        );
+        assert_eq!(
+            table.resolve_named_reference("?bool/foo"),
+            Some(ResourceValue::Boolean(true))
+        );
+        assert!(table
+            .resolve_named_reference("test.app:string/app_name")
+            .is_none());
+        assert!(table.resolve_named_reference("@string/missing").is_none());
+        assert!(table
+            .resolve_named_reference("@android:string/app_name")
+            .is_none());
+
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x01,
+                "framework",
+                &["integer"],
+                &["foo"],
+                &[test_utils::type_(
+                    1,
+                    &test_utils::default_config(),
+                    &[Some(test_utils::simple_entry(
+                        0,
+                        test_utils::VALUE_INT_DEC,
+                        7,
+                    ))],
+                )],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert_eq!(
+            table.resolve_named_reference("@android:integer/foo"),
+            Some(ResourceValue::IntDec(7))
+        );
+    }
+
+    #[test]
+    fn type_id_names() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert_eq!(
+            table.type_id_names("test.app"),
+            Some(vec![(1, "bool".to_owned()), (2, "string".to_owned())])
+        );
+        assert!(table.type_id_names("-").is_none());
+    }
+
+    #[test]
+    fn type_names_independent_of_type_string_pool() {
+        // swap in a type string pool with different contents and order: lookups should still
+        // use the type registry populated during parse
+        let mut table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let mut pkg = table.packages.pop().unwrap();
+        pkg.type_strings = LoadedTable::parse(RESOURCE_ARSC)
+            .unwrap()
+            .packages
+            .pop()
+            .unwrap()
+            .name_strings;
+        let table = LoadedTable {
+            packages: vec![pkg],
+            ..table
+        };
+        assert_eq!(
+            table.type_names("test.app"),
+            Some(vec!["bool".to_owned(), "string".to_owned()])
+        );
+        assert_eq!(
+            table
+                .resid_for_name("test.app", "string", "foo")
+                .map(|resid| resid.into()),
+            Some(0x7f020001)
+        );
+    }
+
+    #[test]
+    fn entries_of_type() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let actual = table
+            .entries_of_type("test.app", "string")
+            .unwrap()
+            .into_iter()
+            .map(|(resid, name)| (resid.into(), name))
+            .collect::<Vec<(u32, String)>>();
+        assert_eq!(
+            actual,
+            vec![
+                (0x7f020000, "app_name".to_owned()),
+                (0x7f020001, "foo".to_owned())
+            ]
+        );
+        assert!(table.entries_of_type("test.app", "-").is_none());
+        assert!(table.entries_of_type("-", "string").is_none());
+    }
+
+    #[test]
+    fn resids_of_type() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let actual = table.resids_of_type("test.app", "string").unwrap();
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0], 0x7f020000);
+        assert_eq!(actual[1], 0x7f020001);
+        assert!(table.resids_of_type("test.app", "-").is_none());
+        assert!(table.resids_of_type("-", "string").is_none());
+    }
+
+    #[test]
+    fn name_for_resid() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert_eq!(
+            table.name_for_resid(&ResourceId::from_u32(0x7f010000)),
+            Some(("test.app".to_owned(), "bool".to_owned(), "foo".to_owned()))
+        );
+    }
+
+    #[test]
+    fn value_for_resid() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let resid = ResourceId::from_u32(0x7f020001); // string/foo
+        let values = table.value_for_resid(&resid).expect("lookup succeeded");
+        assert_eq!(values.len(), 4); // -, sv, en-rXA, ar-rXB
+        let actual = values
+            .iter()
+            .map(|(config, value)| (config.to_qualifier(), value.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(actual[0], ("".to_owned(), "Foo".to_owned()));
+        assert_eq!(actual[1], ("sv".to_owned(), "Bar".to_owned()));
+
+        let resid = ResourceId::from_u32(0x7f010000); // bool/foo
+        let values = table.value_for_resid(&resid).expect("lookup succeeded");
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].1, ResourceValue::Boolean(true));
+
+        assert!(table
+            .value_for_resid(&ResourceId::from_u32(0x7f030000))
+            .is_none());
+    }
+
+    #[test]
+    fn styled_string_for_resid() {
+        let bytes = test_utils::styled_table(
+            &["Hello World", "plain", "b"],
+            &[&[(2, 0, 4)]],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["string"],
+                &["styled", "plain", "number"],
+                &[test_utils::type_(
+                    1,
+                    &test_utils::default_config(),
+                    &[
+                        Some(test_utils::simple_entry(0, test_utils::VALUE_STRING, 0)),
+                        Some(test_utils::simple_entry(1, test_utils::VALUE_STRING, 1)),
+                        Some(test_utils::simple_entry(2, test_utils::VALUE_INT_DEC, 1)),
+                    ],
+                )],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let styled = |resid| {
+            table
+                .styled_string_for_resid(&ResourceId::from_u32(resid))
+                .map(|values| values.into_iter().map(|(_, s)| s).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            styled(0x7f01_0000),
+            Some(vec![StyledString {
+                text: "Hello World".to_owned(),
+                spans: vec![("b".to_owned(), 0, 4)],
+            }])
+        );
+        assert_eq!(
+            styled(0x7f01_0001),
+            Some(vec![StyledString {
+                text: "plain".to_owned(),
+                spans: vec![],
+            }])
+        );
+        assert_eq!(styled(0x7f01_0002), None);
+        assert_eq!(styled(0x7f01_0003), None);
+    }
+
+    #[test]
+    fn is_complex() {
+        // the default value decides, even if a value in another configuration is stored first
+        let sv = test_utils::config([0, u32::from_le_bytes(*b"sv\0\0"), 0, 0, 0, 0, 0, 0]);
+        let bytes = test_utils::table(
+            &["Foo", "Bar"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["array", "string"],
+                &["names", "foo"],
+                &[
+                    test_utils::spec(1, &[0]),
+                    test_utils::type_(
+                        1,
+                        &sv,
+                        &[Some(test_utils::simple_entry(
+                            0,
+                            test_utils::VALUE_STRING,
+                            1,
+                        ))],
+                    ),
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::complex_entry(
+                            0,
+                            0,
+                            &[
+                                (0x0200_0000, test_utils::VALUE_STRING, 0),
+                                (0x0200_0001, test_utils::VALUE_STRING, 1),
+                            ],
+                        ))],
+                    ),
+                    test_utils::spec(2, &[0]),
+                    test_utils::type_(
+                        2,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::simple_entry(
+                            1,
+                            test_utils::VALUE_STRING,
+                            0,
+                        ))],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert_eq!(
+            table.is_complex(&ResourceId::from_u32(0x7f010000)),
+            Some(true)
+        );
+        assert_eq!(
+            table.is_complex(&ResourceId::from_u32(0x7f020000)),
+            Some(false)
+        );
+        assert_eq!(table.is_complex(&ResourceId::from_u32(0x7f020001)), None);
+    }
+
+    #[test]
+    fn entry_flags() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert!(table.entries().all(|e| e.flags().is_empty()));
+
+        let string = |key, flags| {
+            let mut entry = test_utils::simple_entry(key, test_utils::VALUE_STRING, 0);
+            entry[2] = flags; // low byte of the flags
+            Some(entry)
+        };
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["array", "string"],
+                &["names", "public", "weak"],
+                &[
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::complex_entry(0, 0, &[]))],
+                    ),
+                    test_utils::type_(
+                        2,
+                        &test_utils::default_config(),
+                        &[None, string(1, 0x02), string(2, 0x04)],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let actual = table
+            .entries()
+            .map(|e| (e.name().to_owned(), e.flags()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                ("names".to_owned(), EntryFlags::COMPLEX),
+                ("public".to_owned(), EntryFlags::PUBLIC),
+                ("weak".to_owned(), EntryFlags::WEAK),
+            ]
+        );
+
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["string"],
+                &["compact"],
+                &[test_utils::type_(
+                    1,
+                    &test_utils::default_config(),
+                    &[string(0, 0x08)],
+                )],
+            )],
+        );
+        match LoadedTable::parse(&bytes) {
+            Err(Error::Unsupported(what)) => assert_eq!(what, "type 0x01: compact entry"),
+            _ => panic!("expected Error::Unsupported"),
+        }
+    }
+
+    #[test]
+    fn types_without_spec() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert!(table.types_without_spec().is_empty());
+
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["bool", "string"],
+                &["foo"],
+                &[test_utils::type_(
+                    2,
+                    &test_utils::default_config(),
+                    &[Some(test_utils::simple_entry(
+                        0,
+                        test_utils::VALUE_STRING,
+                        0,
+                    ))],
+                )],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert_eq!(table.types_without_spec(), vec![(2, "string".to_owned())]);
+    }
+
+    #[test]
+    fn duplicate_value_strings() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert!(table.duplicate_value_strings().is_empty());
+
+        let bytes = test_utils::table(
+            &["Foo", "Bar", "Foo", "Baz", "Bar", "Foo"],
+            &[test_utils::package(0x7f, "test.app", &[], &[], &[])],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert_eq!(
+            table.duplicate_value_strings(),
+            vec![
+                ("Foo".to_owned(), vec![0, 2, 5]),
+                ("Bar".to_owned(), vec![1, 4])
+            ]
+        );
+    }
+
+    #[test]
+    fn packages() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let packages = table.packages().collect::<Vec<_>>();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].id(), 0x7f);
+        assert_eq!(packages[0].name(), "test.app");
+        assert!(!packages[0].is_dynamic_library());
+    }
+
+    #[test]
+    fn dynamic_library_package() {
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x00,
+                "test.lib",
+                &["string"],
+                &["foo"],
+                &[
+                    test_utils::spec(1, &[0]),
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::simple_entry(
+                            0,
+                            test_utils::VALUE_STRING,
+                            0,
+                        ))],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let package = table.packages().next().unwrap();
+        assert_eq!(package.id(), 0x00);
+        assert!(package.is_dynamic_library());
+        let resid = table.resid_for_name("test.lib", "string", "foo").unwrap();
+        assert_eq!(resid, 0x0001_0000);
+    }
+
+    #[test]
+    fn style_ancestry() {
+        let style = |key, parent| Some(test_utils::complex_entry(key, parent, &[]));
+        // the parents in the default configuration count, even if another configuration is
+        // stored first
+        let night = test_utils::config([0, 0, 0, 0, 0, 0, 0x20 << 8, 0]);
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["style"],
+                &["Base", "Theme", "Theme.Child", "Loop"],
+                &[
+                    test_utils::spec(1, &[0, 0, 0, 0]),
+                    test_utils::type_(1, &night, &[None, None, style(2, 0x7f01_0003), None]),
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[
+                            style(0, 0x0101_0000), // android:style/Theme
+                            style(1, 0x7f01_0000),
+                            style(2, 0x7f01_0001),
+                            style(3, 0x7f01_0003),
+                        ],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let to_u32 = |v: Vec<ResourceId>| v.into_iter().map(u32::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            table.parent_of(&ResourceId::from_u32(0x7f01_0002)).unwrap(),
+            0x7f01_0001
+        );
+        assert_eq!(
+            to_u32(table.style_ancestry(&ResourceId::from_u32(0x7f01_0002))),
+            vec![0x7f01_0002, 0x7f01_0001, 0x7f01_0000]
+        );
+        assert_eq!(
+            to_u32(table.style_ancestry(&ResourceId::from_u32(0x7f01_0003))),
+            vec![0x7f01_0003]
+        );
+        assert!(table
+            .style_ancestry(&ResourceId::from_u32(0x7f01_0004))
+            .is_empty());
+    }
+
+    #[test]
+    fn dangling_references() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert!(table.dangling_references().is_empty());
+
+        let reference = |key, resid| {
+            Some(test_utils::simple_entry(
+                key,
+                test_utils::VALUE_REFERENCE,
+                resid,
+            ))
+        };
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["id", "array"],
+                &["ok", "missing", "null", "framework", "list"],
+                &[
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[
+                            reference(0, 0x7f01_0001),
+                            reference(1, 0x7f01_0010),
+                            reference(2, 0),
+                            reference(3, 0x0101_0000),
+                        ],
+                    ),
+                    test_utils::type_(
+                        2,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::complex_entry(
+                            4,
+                            0,
+                            &[
+                                (0x0200_0000, test_utils::VALUE_REFERENCE, 0x7f01_0000),
+                                (0x0200_0001, test_utils::VALUE_REFERENCE, 0x7f02_0001),
+                            ],
+                        ))],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let actual = table
+            .dangling_references()
+            .into_iter()
+            .map(|(source, target)| (u32::from(source), u32::from(target)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![(0x7f01_0001, 0x7f01_0010), (0x7f02_0000, 0x7f02_0001)]
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let mut table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert!(table.validate().is_empty());
+
+        // string/app_name and string/foo
+        table.packages[0].types[1].entries[1].id = 0;
+        assert_eq!(
+            table.validate(),
+            vec!["package 0x7f type 0x02: entry id 0x0000 follows 0x0000".to_owned()]
+        );
+    }
+
+    #[test]
+    fn validate_string_pool_alignment() {
+        let mut bytes = test_utils::table(
+            &["foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["string"],
+                &["foo"],
+                &[test_utils::type_(
+                    1,
+                    &test_utils::default_config(),
+                    &[Some(test_utils::simple_entry(
+                        0,
+                        test_utils::VALUE_STRING,
+                        0,
+                    ))],
+                )],
+            )],
+        );
+        assert!(LoadedTable::parse(&bytes)
+            .unwrap()
+            .validate_string_pool_alignment()
+            .is_empty());
+
+        // strings_offset of the value string pool, which follows the 12 byte table header
+        bytes[12 + 20] += 2;
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let expected = vec!["Value string pool: strings offset 0x22 not 4-byte aligned".to_owned()];
+        assert_eq!(table.validate_string_pool_alignment(), expected);
+        assert_eq!(table.validate(), expected);
+    }
+
+    #[test]
+    fn string_pool_summary() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert_eq!(
+            table.string_pool_summary(),
+            vec![
+                (PoolKind::Value, true, false),
+                (PoolKind::Type(0x7f), false, false),
+                (PoolKind::Key(0x7f), true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_resolved() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let actual = table
+            .iter_resolved()
+            .map(|(resid, value)| (u32::from(resid), value.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                (0x7f010000, "true".to_owned()),
+                (0x7f020000, "Test app".to_owned()),
+                (0x7f020001, "Foo".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn package_name_collisions() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert!(table.package_name_collisions().is_empty());
+
+        let bytes = test_utils::table(
+            &[],
+            &[
+                test_utils::package(0x7f, "test.app", &[], &[], &[]),
+                test_utils::package(0x01, "android", &[], &[], &[]),
+                test_utils::package(0x02, "test.app", &[], &[], &[]),
+            ],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert_eq!(
+            table.package_name_collisions(),
+            vec![("test.app".to_owned(), vec![0x02, 0x7f])]
+        );
+    }
+
+    #[test]
+    fn attr_enum_name() {
+        let int = |key| Some(test_utils::simple_entry(key, test_utils::VALUE_INT_DEC, 0));
+        let attr = |key, type_, values: &[(u32, u32)]| {
+            let mut map = vec![(0x0100_0000, test_utils::VALUE_INT_DEC, type_)];
+            map.extend(
+                values
+                    .iter()
+                    .map(|(k, v)| (*k, test_utils::VALUE_INT_DEC, *v)),
+            );
+            Some(test_utils::complex_entry(key, 0, &map))
+        };
+        let v21 = test_utils::config([0, 0, 0, 0, 0, 21, 0, 0]);
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["attr", "id"],
+                &["orientation", "gravity", "horizontal", "vertical"],
+                &[
+                    test_utils::spec(1, &[0, 0]),
+                    // only the default configuration's definition counts
+                    test_utils::type_(1, &v21, &[attr(0, 1 << 17, &[]), None]),
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[
+                            attr(0, 1 << 16, &[(0x7f02_0000, 0), (0x7f02_0001, 1)]),
+                            attr(1, 1 << 17, &[(0x7f02_0000, 0), (0x7f02_0001, 1)]),
+                        ],
+                    ),
+                    test_utils::spec(2, &[0, 0]),
+                    test_utils::type_(2, &test_utils::default_config(), &[int(2), int(3)]),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let orientation = ResourceId::from_u32(0x7f01_0000);
+        assert_eq!(
+            table.attr_enum_name(&orientation, 0),
+            Some("horizontal".to_owned())
+        );
+        assert_eq!(
+            table.attr_enum_name(&orientation, 1),
+            Some("vertical".to_owned())
+        );
+        assert_eq!(table.attr_enum_name(&orientation, 2), None);
+        assert_eq!(
+            table.attr_enum_name(&ResourceId::from_u32(0x7f01_0001), 1),
+            None
+        ); // flags, not enum
+        assert_eq!(
+            table.attr_enum_name(&ResourceId::from_u32(0x7f02_0000), 0),
+            None
+        ); // not an attr
+    }
+
+    #[test]
+    fn entries() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let resids = table.resid_iter().collect::<Vec<_>>();
+        let entries = table.entries().collect::<Vec<_>>();
+        assert_eq!(entries.iter().map(|e| e.id()).collect::<Vec<_>>(), resids);
+        for e in &entries {
+            let names = (
+                e.package_name().to_owned(),
+                e.type_name().to_owned(),
+                e.name().to_owned(),
+            );
+            assert_eq!(Some(names), table.name_for_resid(&e.id()));
+            assert_eq!(e.values(), table.value_for_resid(&e.id()));
+            assert_eq!(
+                Some(e.configurations()),
+                table.configurations_for_resid(&e.id())
+            );
+        }
+        let actual = entries
+            .iter()
+            .map(|e| {
+                (
+                    u32::from(e.id()),
+                    format!("{}:{}/{}", e.package_name(), e.type_name(), e.name()),
+                    e.configurations().len(),
+                    e.value().map(|v| v.to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    0x7f01_0000,
+                    "test.app:bool/foo".to_owned(),
+                    1,
+                    Some("true".to_owned())
+                ),
+                (
+                    0x7f02_0000,
+                    "test.app:string/app_name".to_owned(),
+                    3,
+                    Some("Test app".to_owned())
+                ),
+                (
+                    0x7f02_0001,
+                    "test.app:string/foo".to_owned(),
+                    4,
+                    Some("Foo".to_owned())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn resid_iter_filtered() {
+        let entry = |key| Some(test_utils::simple_entry(key, test_utils::VALUE_INT_DEC, 0));
+        let package = |id, name| {
+            test_utils::package(
+                id,
+                name,
+                &["drawable", "string"],
+                &["a", "b"],
+                &[
+                    test_utils::type_(1, &test_utils::default_config(), &[entry(0), entry(1)]),
+                    test_utils::type_(2, &test_utils::default_config(), &[entry(0)]),
+                ],
+            )
+        };
+        let bytes = test_utils::table(&[], &[package(0x01, "android"), package(0x7f, "test.app")]);
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let to_u32 = |iter: super::ResourceIdIterator| iter.map(u32::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            to_u32(table.resid_iter_for_package(0x7f)),
+            vec![0x7f01_0000, 0x7f01_0001, 0x7f02_0000]
+        );
+        assert_eq!(
+            to_u32(table.resid_iter_in_type(0x01, "drawable")),
+            vec![0x0101_0000, 0x0101_0001]
+        );
+        assert_eq!(
+            to_u32(table.resid_iter_in_type(0x7f, "string")),
+            vec![0x7f02_0000]
+        );
+        assert!(to_u32(table.resid_iter_for_package(0x02)).is_empty());
+        assert!(to_u32(table.resid_iter_in_type(0x7f, "layout")).is_empty());
+        assert!(to_u32(table.resid_iter_in_type(0x02, "string")).is_empty());
+    }
+
+    #[test]
+    fn resid_iter_varying() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let actual = table
+            .resid_iter_varying(ConfigurationFlags::LOCALE)
+            .map(u32::from)
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec![0x7f020000, 0x7f020001]); // string/app_name, string/foo
+        assert_eq!(
+            table
+                .resid_iter_varying(ConfigurationFlags::DENSITY | ConfigurationFlags::VERSION)
+                .count(),
+            0
+        );
+
+        let string = |key| Some(test_utils::simple_entry(key, test_utils::VALUE_STRING, 0));
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["string"],
+                &["a", "b", "c"],
+                &[
+                    test_utils::spec(1, &[0x0004, 0x0100, 0x0104]),
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[string(0), string(1), string(2)],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let actual = table
+            .resid_iter_varying(ConfigurationFlags::LOCALE)
+            .map(u32::from)
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec![0x7f010000, 0x7f010002]);
+    }
+
+    #[test]
+    fn parse_stale_string_pool_offsets() {
+        let mut package = test_utils::package(
+            0x7f,
+            "test.app",
+            &["string"],
+            &["foo"],
+            &[
+                test_utils::spec(1, &[0]),
+                test_utils::type_(
+                    1,
+                    &test_utils::default_config(),
+                    &[Some(test_utils::simple_entry(
+                        0,
+                        test_utils::VALUE_STRING,
+                        0,
+                    ))],
+                ),
+            ],
+        );
+        // zero typeStrings and keyStrings, located after the chunk header, id and name
+        package[268..272].copy_from_slice(&[0; 4]);
+        package[276..280].copy_from_slice(&[0; 4]);
+        let bytes = test_utils::table(&["Foo"], &[package]);
+
+        assert!(LoadedTable::parse(&bytes).is_err());
+
+        let table = LoadedTable::parse_with_options(&bytes, lenient()).unwrap();
+        assert_eq!(table.warnings().len(), 2);
+        assert_eq!(
+            table.resid_for_name("test.app", "string", "foo").unwrap(),
+            0x7f010000
+        );
+
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        assert!(table.warnings().is_empty());
+    }
+
+    #[test]
+    fn locale_coverage() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let coverage = table.locale_coverage();
+        let actual = coverage
+            .iter()
+            .map(|(locale, count)| (locale.as_str(), *count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![("ar-XB", 2), ("default", 2), ("en-XA", 2), ("sv", 1)]
+        );
+    }
+
+    #[test]
+    fn parse_sparse_type() {
+        let string = |key| Some(test_utils::simple_entry(key, test_utils::VALUE_STRING, 0));
+        let mut sparse = test_utils::type_(2, &test_utils::default_config(), &[string(1)]);
+        sparse[9] = 0x01; // FLAG_SPARSE, after the chunk header and type id
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["bool", "string"],
+                &["foo", "bar"],
+                &[
+                    test_utils::spec(1, &[0]),
+                    test_utils::type_(1, &test_utils::default_config(), &[string(0)]),
+                    test_utils::spec(2, &[0]),
+                    sparse,
+                ],
+            )],
+        );
+
+        match LoadedTable::parse(&bytes) {
+            Err(Error::Unsupported(_)) => {}
+            _ => panic!("expected Error::Unsupported"),
+        }
+
+        let skip_unsupported = ParseOptions {
+            skip_unsupported: true,
+            ..ParseOptions::default()
+        };
+        for options in &[lenient(), skip_unsupported] {
+            let table = LoadedTable::parse_with_options(&bytes, *options).unwrap();
+            assert_eq!(table.warnings().len(), 1);
+            assert_eq!(
+                table.resid_iter().map(u32::from).collect::<Vec<_>>(),
+                vec![0x7f010000]
+            );
+        }
+    }
+
+    #[test]
+    fn rename_type() {
+        let mut table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        table.rename_type("test.app", "string", "text").unwrap();
+        assert_eq!(
+            table.type_names("test.app").unwrap(),
+            vec!["bool".to_owned(), "text".to_owned()]
+        );
+        assert_eq!(
+            table.resid_for_name("test.app", "text", "foo").unwrap(),
+            0x7f020001
+        );
+        assert!(table.resid_for_name("test.app", "string", "foo").is_none());
+
+        assert!(table.rename_type("test.app", "text", "bool").is_err());
+        assert!(table.rename_type("test.app", "string", "foo").is_err());
+        assert!(table.rename_type("android", "bool", "foo").is_err());
+        assert_eq!(
+            table.type_names("test.app").unwrap(),
+            vec!["bool".to_owned(), "text".to_owned()]
+        );
+    }
+
+    #[test]
+    fn complex_entry_with_zero_key() {
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["style"],
+                &["Theme"],
+                &[
+                    test_utils::spec(1, &[0]),
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::complex_entry(
+                            0,
+                            0,
+                            &[
+                                (0, test_utils::VALUE_INT_DEC, 1),
+                                (0x0101_0000, test_utils::VALUE_INT_DEC, 2),
+                                (0xffff_ffff, 0xff, 3), // unknown value type
+                            ],
+                        ))],
+                    ),
+                ],
+            )],
+        );
+        let resid = ResourceId::from_u32(0x7f010000);
+
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert!(table.value_for_resid(&resid).is_none());
+
+        let table = LoadedTable::parse_with_options(&bytes, lenient()).unwrap();
+        let values = table.value_for_resid(&resid).unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(
+            values[0].1,
+            ResourceValue::Array(vec![(
+                ResourceId::from_u32(0x0101_0000),
+                ResourceValue::IntDec(2)
+            )])
+        );
+    }
+
+    #[test]
+    fn spec_and_type_entry_count_mismatch() {
+        let string = |key| Some(test_utils::simple_entry(key, test_utils::VALUE_STRING, 0));
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["string"],
+                &["foo", "bar"],
+                &[
+                    test_utils::spec(1, &[0]),
+                    test_utils::type_(1, &test_utils::default_config(), &[string(0), string(1)]),
+                ],
+            )],
+        );
+
+        match LoadedTable::parse(&bytes) {
+            Err(Error::CorruptData(msg)) => {
+                assert_eq!(msg, "type 0x01: spec has 1 entries, type has 2")
+            }
+            _ => panic!("expected Error::CorruptData"),
+        }
+
+        let table = LoadedTable::parse_with_options(&bytes, lenient()).unwrap();
+        assert_eq!(table.warnings().len(), 1);
+        assert_eq!(table.resid_iter().count(), 2);
+    }
+
+    #[test]
+    fn flatten() {
+        let reference = |key, target| {
+            Some(test_utils::simple_entry(
+                key,
+                test_utils::VALUE_REFERENCE,
+                target,
+            ))
+        };
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["string"],
+                &["foo", "alias", "alias_alias", "dangling", "loop"],
+                &[
+                    test_utils::spec(1, &[0; 5]),
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[
+                            Some(test_utils::simple_entry(0, test_utils::VALUE_STRING, 0)),
+                            reference(1, 0x7f01_0000),
+                            reference(2, 0x7f01_0001),
+                            reference(3, 0x7f01_ffff),
+                            reference(4, 0x7f01_0004),
+                        ],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let actual = table
+            .flatten(8)
+            .into_iter()
+            .map(|(resid, value)| (u32::from(resid), value.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                (0x7f01_0000, "Foo".to_owned()),
+                (0x7f01_0001, "Foo".to_owned()),
+                (0x7f01_0002, "Foo".to_owned()),
+                (0x7f01_0003, "@0x7f01ffff".to_owned()),
+                (0x7f01_0004, "@0x7f010004".to_owned()),
+            ]
+        );
+
+        let flat = table.flatten(1);
+        assert_eq!(
+            flat[&ResourceId::from_u32(0x7f01_0002)],
+            ResourceValue::Reference(ResourceId::from_u32(0x7f01_0000))
+        );
+    }
+
+    #[test]
+    fn resolve_reference() {
+        let reference = |key, target| {
+            Some(test_utils::simple_entry(
+                key,
+                test_utils::VALUE_REFERENCE,
+                target,
+            ))
+        };
+        let config = test_utils::config([0, u32::from_le_bytes(*b"sv\0\0"), 0, 0, 0, 0, 0, 0]);
+        let bytes = test_utils::table(
+            &["Foo", "Bar"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["string"],
+                &[
+                    "foo",
+                    "alias",
+                    "alias_alias",
+                    "dangling",
+                    "loop_a",
+                    "loop_b",
+                ],
+                &[
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[
+                            Some(test_utils::simple_entry(0, test_utils::VALUE_STRING, 0)),
+                            reference(1, 0x7f01_0000),
+                            reference(2, 0x7f01_0001),
+                            reference(3, 0x7f01_ffff),
+                            reference(4, 0x7f01_0005),
+                            reference(5, 0x7f01_0004),
+                        ],
+                    ),
+                    test_utils::type_(
+                        1,
+                        &config,
+                        &[
+                            Some(test_utils::simple_entry(0, test_utils::VALUE_STRING, 1)),
+                            None,
+                            reference(2, 0x7f01_0000),
+                        ],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let default = ResourceConfiguration::default();
+        let sv = ResourceConfiguration::parse_qualifier("sv").unwrap();
+        let resolve = |resid, config| {
+            table
+                .resolve_reference(&ResourceId::from_u32(resid), config)
+                .map(|value| value.map(|value| value.to_string()))
+        };
+
+        assert_eq!(resolve(0x7f01_0000, &default).unwrap().unwrap(), "Foo");
+        assert_eq!(resolve(0x7f01_0002, &default).unwrap().unwrap(), "Foo");
+        assert_eq!(resolve(0x7f01_0000, &sv).unwrap().unwrap(), "Bar");
+        // only the first lookup uses the given configuration; the hops use the default one
+        assert_eq!(resolve(0x7f01_0001, &sv).unwrap().unwrap(), "Foo");
+        assert_eq!(resolve(0x7f01_0002, &sv).unwrap().unwrap(), "Foo");
+        assert_eq!(resolve(0x7f01_0003, &default).unwrap(), None);
+        assert_eq!(resolve(0x7f01_ffff, &default).unwrap(), None);
+        match resolve(0x7f01_0004, &default) {
+            Err(Error::CorruptData(_)) => {}
+            x => panic!("expected Error::CorruptData, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn configurations_from_chunks() {
+        let table = LoadedTable::parse(RESOURCE_ARSC).unwrap();
+        let resid = ResourceId::from_u32(0x7f020001); // string/foo
+        let actual = table
+            .value_for_resid(&resid)
+            .unwrap()
+            .iter()
+            .map(|(config, _)| config.to_qualifier())
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec!["", "sv", "en-rXA", "ar-rXB"]);
+    }
+
+    #[test]
+    fn string_index_out_of_range() {
+        let bytes = test_utils::table(
+            &["Foo"],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["string"],
+                &["foo", "bar"],
+                &[
+                    test_utils::spec(1, &[0, 0]),
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[
+                            Some(test_utils::simple_entry(0, test_utils::VALUE_STRING, 0)),
+                            Some(test_utils::simple_entry(1, test_utils::VALUE_STRING, 1)),
+                        ],
+                    ),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let resid = ResourceId::from_u32(0x7f01_0001);
+        let e = table.entry(&resid).unwrap();
+        match table.loaded_value_to_res_value(0x7f, &e.values[0].1) {
+            Err(Error::CorruptData(msg)) => {
+                assert_eq!(msg, "string index 1 out of range (1 strings)")
+            }
+            _ => panic!("expected Error::CorruptData"),
+        }
+        assert!(table.value_for_resid(&resid).is_none());
+        assert_eq!(table.iter_resolved().count(), 1);
+    }
+
+    #[test]
+    fn largest_value_strings() {
+        let bytes = test_utils::table(
+            &["a", "abcd", "ab", "wxyz", "abc"],
+            &[test_utils::package(0x7f, "test.app", &[], &[], &[])],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert_eq!(
+            table.largest_value_strings(3),
+            vec![
+                (1, 4, "abcd".to_owned()),
+                (3, 4, "wxyz".to_owned()),
+                (4, 3, "abc".to_owned()),
+            ]
+        );
+        assert_eq!(table.largest_value_strings(10).len(), 5);
+        assert!(table.largest_value_strings(0).is_empty());
+    }
+
+    #[test]
+    fn dynamic_references() {
+        let dynamic_ref = |key, resid| {
+            Some(test_utils::simple_entry(
+                key,
+                test_utils::VALUE_DYNAMIC_REFERENCE,
+                resid,
+            ))
+        };
+        let bytes = test_utils::table(
+            &[],
+            &[
+                test_utils::package(
+                    0x7f,
+                    "test.app",
+                    &["id"],
+                    &["lib", "own", "missing"],
+                    &[
+                        test_utils::library(&[(0x02, "test.lib"), (0x05, "test.missing")]),
+                        test_utils::type_(
+                            1,
+                            &test_utils::default_config(),
+                            &[
+                                dynamic_ref(0, 0x0201_0000),
+                                dynamic_ref(1, 0x0001_0001),
+                                dynamic_ref(2, 0x0501_0000),
+                            ],
+                        ),
+                    ],
+                ),
+                test_utils::package(
+                    0x03,
+                    "test.lib",
+                    &["id"],
+                    &["foo"],
+                    &[test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::simple_entry(
+                            0,
+                            test_utils::VALUE_INT_DEC,
+                            0,
+                        ))],
+                    )],
+                ),
+            ],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+
+        let app = table.packages().find(|p| p.id() == 0x7f).unwrap();
+        assert_eq!(
+            app.libraries(),
+            &[
+                (0x02, "test.lib".to_owned()),
+                (0x05, "test.missing".to_owned())
+            ]
+        );
+
+        let value = |resid| table.default_value(&ResourceId::from_u32(resid));
+        assert_eq!(
+            value(0x7f01_0000),
+            Some(ResourceValue::Reference(ResourceId::from_u32(0x0301_0000)))
+        );
+        assert_eq!(
+            value(0x7f01_0001),
+            Some(ResourceValue::Reference(ResourceId::from_u32(0x7f01_0001)))
+        );
+        assert_eq!(
+            value(0x7f01_0002),
+            Some(ResourceValue::Reference(ResourceId::from_u32(0x0501_0000)))
+        );
+    }
+
+    #[test]
+    fn entry_out_of_bounds() {
+        let mut bytes = test_utils::type_(
+            1,
+            &test_utils::default_config(),
+            &[Some(test_utils::simple_entry(
+                0,
+                test_utils::VALUE_INT_DEC,
+                0,
+            ))],
+        );
+        assert!(LoadedTable::parse_type(Chunk::Type(&bytes)).is_ok());
+
+        // point entries_offset past the end of the chunk
+        bytes[16..20].copy_from_slice(&0x1000_u32.to_le_bytes());
+        match LoadedTable::parse_type(Chunk::Type(&bytes)) {
+            Err(Error::CorruptData(msg)) => assert_eq!(msg, "Entry at 0x1000 out of bounds"),
+            _ => panic!("expected Error::CorruptData"),
+        }
+    }
+
+    #[test]
+    fn malformed_package_children() {
+        let parse = |children: &[Vec<u8>]| {
+            let bytes = test_utils::table(
+                &[],
+                &[test_utils::package(
+                    0x7f,
+                    "test.app",
+                    &["integer"],
+                    &["foo"],
+                    children,
+                )],
+            );
+            LoadedTable::parse(&bytes).map(|_| ())
+        };
+        let int = |key| Some(test_utils::simple_entry(key, test_utils::VALUE_INT_DEC, 0));
+        let config = test_utils::default_config();
+        assert!(parse(&[test_utils::type_(1, &config, &[int(0)])]).is_ok());
+
+        // a type chunk that is all header
+        match parse(&[vec![0x01, 0x02, 0x08, 0x00, 0x08, 0x00, 0x00, 0x00]]) {
+            Err(Error::CorruptData(msg)) => assert_eq!(msg, "Type at 0x0 out of bounds"),
+            x => panic!("expected Error::CorruptData, got {:?}", x),
+        }
+
+        // an entry whose key is not in the key string pool
+        match parse(&[test_utils::type_(1, &config, &[int(99)])]) {
+            Err(Error::CorruptData(msg)) => {
+                assert_eq!(msg, "type 0x01: key index 99 out of range (1 strings)")
+            }
+            x => panic!("expected Error::CorruptData, got {:?}", x),
+        }
+
+        // type ids start at 1
+        match parse(&[test_utils::type_(0, &config, &[int(0)])]) {
+            Err(Error::CorruptData(msg)) => assert_eq!(msg, "type id 0"),
+            x => panic!("expected Error::CorruptData, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn legacy_config_size() {
+        // a 0x1c byte configuration ends after the version field
+        let mut legacy = test_utils::config([0, 0x0000_6e65, 0, 0, 0, 21, 0, 0])[..0x1c].to_vec();
+        legacy[0..4].copy_from_slice(&0x1c_u32.to_le_bytes());
+        let int = |data| Some(test_utils::simple_entry(0, test_utils::VALUE_INT_DEC, data));
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["integer"],
+                &["foo"],
+                &[
+                    test_utils::type_(1, &test_utils::default_config(), &[int(1)]),
+                    test_utils::type_(1, &legacy, &[int(2)]),
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let actual = table
+            .value_for_resid(&ResourceId::from_u32(0x7f01_0000))
+            .unwrap()
+            .into_iter()
+            .map(|(config, value)| (config.to_qualifier(), value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                ("".to_owned(), ResourceValue::IntDec(1)),
+                ("en-v21".to_owned(), ResourceValue::IntDec(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn lenient_parsing() {
+        let package = |children: &[Vec<u8>]| {
+            test_utils::package(0x7f, "test.app", &["integer"], &["foo"], children)
+        };
+        let integers = test_utils::type_(
+            1,
+            &test_utils::default_config(),
+            &[Some(test_utils::simple_entry(
+                0,
+                test_utils::VALUE_INT_DEC,
+                1,
+            ))],
+        );
+        // a spec chunk directly in the table, counted as a second package, and a table chunk in
+        // the package
+        let bytes = test_utils::table(
+            &[],
+            &[
+                package(&[test_utils::table(&[], &[]), integers]),
+                test_utils::spec(1, &[0]),
+            ],
+        );
+        match LoadedTable::parse(&bytes) {
+            Err(Error::UnexpectedChunk) => {}
+            _ => panic!("expected Error::UnexpectedChunk"),
+        }
+
+        let table = LoadedTable::parse_with_options(&bytes, lenient()).unwrap();
+        assert_eq!(
+            table.warnings(),
+            &[
+                "skipping chunk of type 0x0002: unexpected chunk",
+                "skipping chunk of type 0x0202: unexpected chunk",
+                "expected 2 packages, found 1",
+            ]
+        );
+        assert_eq!(
+            table.default_value(&ResourceId::from_u32(0x7f01_0000)),
+            Some(ResourceValue::IntDec(1))
+        );
+
+        // a package with a corrupt key string pool is dropped, the other one kept
+        let broken = test_utils::package(0x02, "test.broken", &["integer"], &["foo"], &[]);
+        let mut bytes = test_utils::table(&[], &[package(&[]), broken]);
+        let len = bytes.len();
+        bytes[len - 0x1c..].iter_mut().for_each(|b| *b = 0xff);
+        assert!(LoadedTable::parse(&bytes).is_err());
+        let skip_unsupported = ParseOptions {
+            skip_unsupported: true,
+            ..ParseOptions::default()
+        };
+        assert!(LoadedTable::parse_with_options(&bytes, skip_unsupported).is_err());
+        let table = LoadedTable::parse_with_options(&bytes, lenient()).unwrap();
+        assert_eq!(table.package_names(), vec!["test.app".to_owned()]);
+        assert_eq!(
+            table.warnings(),
+            &[
+                "skipping package: corrupt data: 1 string and 4294967295 style offsets do not fit \
+                 in 40 bytes",
+                "expected 2 packages, found 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn typed_values() {
+        const VALUE_DIMENSION: u8 = 0x05;
+        const VALUE_FRACTION: u8 = 0x06;
+        const VALUE_COLOR_ARGB8: u8 = 0x1c;
+        const VALUE_COLOR_RGB8: u8 = 0x1d;
+        const VALUE_COLOR_ARGB4: u8 = 0x1e;
+        const VALUE_COLOR_RGB4: u8 = 0x1f;
+        let values = [
+            (VALUE_DIMENSION, 0x0000_1001),   // 16, radix 23p0, dp
+            (VALUE_DIMENSION, 0x0000_c012),   // 192, radix 16p7, sp
+            (VALUE_DIMENSION, 0xffff_fe00),   // -2, radix 23p0, px
+            (VALUE_FRACTION, 0x4000_0030),    // 0x400000, radix 0p23, %
+            (VALUE_FRACTION, 0x4000_0031),    // 0x400000, radix 0p23, %p
+            (VALUE_COLOR_ARGB8, 0x80ff_8000), // #80ff8000
+            (VALUE_COLOR_RGB8, 0xff00_80ff),  // #0080ff
+            (VALUE_COLOR_ARGB4, 0x88ff_0000), // #8f00
+            (VALUE_COLOR_RGB4, 0xff00_ff00),  // #0f0
+            (VALUE_DIMENSION, 0x0000_1007),   // bad unit
+        ];
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["dimen"],
+                &["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"],
+                &[test_utils::type_(
+                    1,
+                    &test_utils::default_config(),
+                    &values
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (type_, data))| {
+                            Some(test_utils::simple_entry(i as u32, *type_, *data))
+                        })
+                        .collect::<Vec<_>>(),
+                )],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        let value = |entry_id| table.default_value(&ResourceId::from_parts(0x7f, 1, entry_id));
+
+        assert_eq!(
+            value(0),
+            Some(ResourceValue::Dimension(16.0, DimensionUnit::Dp))
+        );
+        assert_eq!(
+            value(1),
+            Some(ResourceValue::Dimension(1.5, DimensionUnit::Sp))
+        );
+        assert_eq!(
+            value(2),
+            Some(ResourceValue::Dimension(-2.0, DimensionUnit::Px))
+        );
+        assert_eq!(
+            value(3),
+            Some(ResourceValue::Fraction(0.5, FractionUnit::Fraction))
+        );
+        assert_eq!(value(4).unwrap().to_string(), "50%p");
+        assert_eq!(
+            value(5),
+            Some(ResourceValue::ColorArgb8(
+                128.0 / 255.0,
+                1.0,
+                128.0 / 255.0,
+                0.0
+            ))
+        );
+        assert_eq!(value(5).unwrap().to_string(), "#80ff8000");
+        assert_eq!(value(6).unwrap().to_string(), "#0080ff");
+        assert_eq!(value(7).unwrap().to_string(), "#8f00");
+        assert_eq!(value(8).unwrap().to_string(), "#0f0");
+        assert_eq!(value(9), None);
+    }
+
+    #[test]
+    fn color_channels() {
+        // Android packs colors as 0xAARRGGBB
+        assert_eq!(super::color_components(0xff00_00ff), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(super::color_components(0x00ff_0000), [0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(super::color_components(0x0000_ff00), [0.0, 0.0, 1.0, 0.0]);
+        // straight alpha: a half transparent red keeps its full red channel
+        assert_eq!(
+            super::color_components(0x80ff_0000),
+            [128.0 / 255.0, 1.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn null_and_xml_chunks() {
+        let null_chunk = vec![0x00, 0x00, 0x08, 0x00, 0x08, 0x00, 0x00, 0x00];
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &["integer"],
+                &["foo"],
+                &[
+                    null_chunk.clone(),
+                    test_utils::type_(
+                        1,
+                        &test_utils::default_config(),
+                        &[Some(test_utils::simple_entry(
+                            0,
+                            test_utils::VALUE_INT_DEC,
+                            1,
+                        ))],
+                    ),
+                    null_chunk,
+                ],
+            )],
+        );
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert_eq!(
+            table.default_value(&ResourceId::from_u32(0x7f01_0000)),
+            Some(ResourceValue::IntDec(1))
+        );
+
+        let xml_chunk = vec![0x03, 0x00, 0x08, 0x00, 0x08, 0x00, 0x00, 0x00];
+        let bytes = test_utils::table(
+            &[],
+            &[test_utils::package(
+                0x7f,
+                "test.app",
+                &[],
+                &[],
+                &[xml_chunk],
+            )],
+        );
+        match LoadedTable::parse(&bytes) {
+            Err(Error::CorruptData(msg)) => assert!(msg.contains("binary XML"), "{}", msg),
+            _ => panic!("expected Error::CorruptData"),
+        }
+    }
+
+    #[test]
+    fn trailing_padding() {
+        let mut bytes = RESOURCE_ARSC.to_vec();
+        bytes.extend_from_slice(&[0; 16]);
+        let table = LoadedTable::parse(&bytes).unwrap();
+        assert_eq!(table.packages.len(), 1);
+
+        bytes[RESOURCE_ARSC.len() + 7] = 0x01;
+        match LoadedTable::parse(&bytes) {
+            Err(Error::CorruptData(msg)) => assert_eq!(msg, "trailing data after table"),
+            _ => panic!("expected Error::CorruptData"),
+        }
     }
 }
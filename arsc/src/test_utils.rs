@@ -0,0 +1,243 @@
+//! Helpers to assemble small, synthetic arsc files for unit tests. The helpers only produce
+//! well-formed data; tests that need malformed input patch the resulting bytes.
+
+const TYPE_STRING_POOL: u16 = 0x0001;
+const TYPE_TABLE: u16 = 0x0002;
+const TYPE_PACKAGE: u16 = 0x0200;
+const TYPE_TYPE: u16 = 0x0201;
+const TYPE_SPEC: u16 = 0x0202;
+const TYPE_LIBRARY: u16 = 0x0203;
+
+pub const VALUE_REFERENCE: u8 = 0x01;
+pub const VALUE_STRING: u8 = 0x03;
+pub const VALUE_DYNAMIC_REFERENCE: u8 = 0x07;
+pub const VALUE_INT_DEC: u8 = 0x10;
+
+fn push_u16(v: &mut Vec<u8>, value: u16) {
+    v.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(v: &mut Vec<u8>, value: u32) {
+    v.extend_from_slice(&value.to_le_bytes());
+}
+
+fn pad4(v: &mut Vec<u8>) {
+    while v.len() & 0x03 != 0 {
+        v.push(0);
+    }
+}
+
+fn chunk(type_: u16, header: &[u8], body: &[u8]) -> Vec<u8> {
+    let header_size = 8 + header.len();
+    let mut v = Vec::with_capacity(header_size + body.len());
+    push_u16(&mut v, type_);
+    push_u16(&mut v, header_size as u16);
+    push_u32(&mut v, (header_size + body.len()) as u32);
+    v.extend_from_slice(header);
+    v.extend_from_slice(body);
+    v
+}
+
+pub fn string_pool(strings: &[&str], utf8: bool) -> Vec<u8> {
+    styled_string_pool(strings, &[], utf8)
+}
+
+/// A string pool where the i'th string is styled by the (name index, first char, last char)
+/// spans in `styles[i]`.
+pub fn styled_string_pool(strings: &[&str], styles: &[&[(u32, u32, u32)]], utf8: bool) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut offsets = Vec::new();
+    for s in strings {
+        push_u32(&mut offsets, data.len() as u32);
+        if utf8 {
+            // the UTF-16 length followed by the UTF-8 length, each in one or two bytes
+            let push_len = |data: &mut Vec<u8>, len: usize| {
+                assert!(len < 0x8000);
+                if len >= 0x80 {
+                    data.push(0x80 | (len >> 8) as u8);
+                }
+                data.push(len as u8);
+            };
+            push_len(&mut data, s.encode_utf16().count());
+            push_len(&mut data, s.len());
+            data.extend_from_slice(s.as_bytes());
+            data.push(0);
+        } else {
+            let units = s.encode_utf16().collect::<Vec<_>>();
+            assert!(units.len() < 0x8000);
+            push_u16(&mut data, units.len() as u16);
+            units.iter().for_each(|u| push_u16(&mut data, *u));
+            push_u16(&mut data, 0);
+        }
+    }
+    pad4(&mut data);
+
+    let mut style_data = Vec::new();
+    for spans in styles {
+        push_u32(&mut offsets, style_data.len() as u32);
+        for (name, first, last) in spans.iter() {
+            push_u32(&mut style_data, *name);
+            push_u32(&mut style_data, *first);
+            push_u32(&mut style_data, *last);
+        }
+        push_u32(&mut style_data, 0xffff_ffff);
+    }
+
+    let header_size = 8 + 20;
+    let strings_offset = header_size + offsets.len();
+    let mut header = Vec::new();
+    push_u32(&mut header, strings.len() as u32);
+    push_u32(&mut header, styles.len() as u32);
+    push_u32(&mut header, if utf8 { 1 << 8 } else { 0 });
+    push_u32(&mut header, strings_offset as u32);
+    push_u32(
+        &mut header,
+        if styles.is_empty() {
+            0
+        } else {
+            (strings_offset + data.len()) as u32
+        },
+    );
+
+    let mut body = offsets;
+    body.extend_from_slice(&data);
+    body.extend_from_slice(&style_data);
+    chunk(TYPE_STRING_POOL, &header, &body)
+}
+
+/// A 0x40 byte configuration with the given imsi, locale, screen_type, input, screen_size,
+/// version, screen_config and screen_size_dp fields.
+pub fn config(fields: [u32; 8]) -> Vec<u8> {
+    let mut v = Vec::with_capacity(0x40);
+    push_u32(&mut v, 0x40);
+    fields.iter().for_each(|f| push_u32(&mut v, *f));
+    v.resize(0x40, 0);
+    v
+}
+
+pub fn default_config() -> Vec<u8> {
+    config([0; 8])
+}
+
+pub fn simple_entry(key: u32, type_: u8, data: u32) -> Vec<u8> {
+    let mut v = Vec::new();
+    push_u16(&mut v, 8);
+    push_u16(&mut v, 0);
+    push_u32(&mut v, key);
+    push_u16(&mut v, 8);
+    v.push(0);
+    v.push(type_);
+    push_u32(&mut v, data);
+    v
+}
+
+pub fn complex_entry(key: u32, parent: u32, map: &[(u32, u8, u32)]) -> Vec<u8> {
+    let mut v = Vec::new();
+    push_u16(&mut v, 16);
+    push_u16(&mut v, 0x01); // FLAG_COMPLEX
+    push_u32(&mut v, key);
+    push_u32(&mut v, parent);
+    push_u32(&mut v, map.len() as u32);
+    for (name, type_, data) in map {
+        push_u32(&mut v, *name);
+        push_u16(&mut v, 8);
+        v.push(0);
+        v.push(*type_);
+        push_u32(&mut v, *data);
+    }
+    v
+}
+
+pub fn spec(id: u8, flags: &[u32]) -> Vec<u8> {
+    let mut header = vec![id, 0, 0, 0];
+    push_u32(&mut header, flags.len() as u32);
+    let mut body = Vec::new();
+    flags.iter().for_each(|f| push_u32(&mut body, *f));
+    chunk(TYPE_SPEC, &header, &body)
+}
+
+pub fn type_(id: u8, config: &[u8], entries: &[Option<Vec<u8>>]) -> Vec<u8> {
+    let header_size = 8 + 12 + config.len();
+    let mut offsets = Vec::new();
+    let mut data = Vec::new();
+    for entry in entries {
+        match entry {
+            Some(bytes) => {
+                push_u32(&mut offsets, data.len() as u32);
+                data.extend_from_slice(bytes);
+            }
+            None => push_u32(&mut offsets, 0xffff_ffff),
+        }
+    }
+
+    let mut header = vec![id, 0, 0, 0];
+    push_u32(&mut header, entries.len() as u32);
+    push_u32(&mut header, (header_size + offsets.len()) as u32);
+    header.extend_from_slice(config);
+
+    let mut body = offsets;
+    body.extend_from_slice(&data);
+    chunk(TYPE_TYPE, &header, &body)
+}
+
+/// A library chunk mapping build time package ids to package names.
+pub fn library(entries: &[(u32, &str)]) -> Vec<u8> {
+    let mut header = Vec::new();
+    push_u32(&mut header, entries.len() as u32);
+    let mut body = Vec::new();
+    for (id, name) in entries {
+        push_u32(&mut body, *id);
+        let mut name_units = name.encode_utf16().collect::<Vec<_>>();
+        assert!(name_units.len() < 128);
+        name_units.resize(128, 0);
+        name_units.iter().for_each(|u| push_u16(&mut body, *u));
+    }
+    chunk(TYPE_LIBRARY, &header, &body)
+}
+
+/// A package chunk: the type and key string pools are placed first, followed by `children`
+/// (spec and type chunks) in the order given.
+pub fn package(
+    id: u32,
+    name: &str,
+    type_strings: &[&str],
+    key_strings: &[&str],
+    children: &[Vec<u8>],
+) -> Vec<u8> {
+    let header_size = 8 + 4 + 256 + 16;
+    let type_pool = string_pool(type_strings, false);
+    let key_pool = string_pool(key_strings, true);
+
+    let mut header = Vec::new();
+    push_u32(&mut header, id);
+    let mut name_units = name.encode_utf16().collect::<Vec<_>>();
+    assert!(name_units.len() < 128);
+    name_units.resize(128, 0);
+    name_units.iter().for_each(|u| push_u16(&mut header, *u));
+    push_u32(&mut header, header_size as u32);
+    push_u32(&mut header, 0);
+    push_u32(&mut header, (header_size + type_pool.len()) as u32);
+    push_u32(&mut header, 0);
+
+    let mut body = type_pool;
+    body.extend_from_slice(&key_pool);
+    children.iter().for_each(|c| body.extend_from_slice(c));
+    chunk(TYPE_PACKAGE, &header, &body)
+}
+
+pub fn table(value_strings: &[&str], packages: &[Vec<u8>]) -> Vec<u8> {
+    styled_table(value_strings, &[], packages)
+}
+
+/// A table whose value string pool is styled as by `styled_string_pool`.
+pub fn styled_table(
+    value_strings: &[&str],
+    styles: &[&[(u32, u32, u32)]],
+    packages: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut header = Vec::new();
+    push_u32(&mut header, packages.len() as u32);
+    let mut body = styled_string_pool(value_strings, styles, true);
+    packages.iter().for_each(|p| body.extend_from_slice(p));
+    chunk(TYPE_TABLE, &header, &body)
+}
@@ -1,46 +1,471 @@
-use arsc::Table;
-use clap::{value_t, App, Arg};
-use memmap::MmapOptions;
+use arsc::{Error, ParseOptions, ResourceConfiguration, ResourceId, Table};
+use clap::{value_t, App, AppSettings, Arg, ArgMatches, SubCommand};
+use memmap::{Mmap, MmapOptions};
+use std::borrow::Cow;
+use std::env;
 use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+use zip::result::ZipError;
 use zip::{CompressionMethod, ZipArchive};
 
+// the header of a resource table chunk: type RES_TABLE_TYPE, header size 12
+const TABLE_MAGIC: &[u8] = &[0x02, 0x00, 0x0c, 0x00];
+
+// where to look for the resource table if no explicit entry name is given: APKs store it at the
+// root of the archive, App Bundle base modules under base/
+const DEFAULT_ENTRY_NAMES: &[&str] = &["resources.arsc", "base/resources.arsc"];
+
 fn main() {
+    match run() {
+        Ok(()) => {}
+        // the reader went away, e.g. `arsc dump app.apk | head`: not an error
+        Err(Error::IoError(e)) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run() -> Result<(), Error> {
     // parse command line arguments
+    let apk_args = [
+        Arg::with_name("apk")
+            .takes_value(true)
+            .required(true)
+            .help("APK, App Bundle or other zip, or a bare resources.arsc"),
+        Arg::with_name("entry")
+            .long("entry")
+            .takes_value(true)
+            .help("Name of the zip entry holding the resource table"),
+        Arg::with_name("raw")
+            .long("raw")
+            .conflicts_with("entry")
+            .help("Parse the file as a resource table instead of looking for one in a zip"),
+        Arg::with_name("fail-on-unsupported")
+            .long("fail-on-unsupported")
+            .help("Exit with an error instead of skipping data this tool cannot handle"),
+    ];
+    let subcommands = vec![
+        SubCommand::with_name("dump")
+            .about("Print all resources and their values")
+            .args(&apk_args)
+            .arg(
+                Arg::with_name("type")
+                    .long("type")
+                    .takes_value(true)
+                    .help("Only dump resources of this type, e.g. string"),
+            )
+            .arg(
+                Arg::with_name("values-only")
+                    .long("values-only")
+                    .help("Only print the default configuration value of each resource"),
+            )
+            .arg(
+                Arg::with_name("no-values")
+                    .long("no-values")
+                    .conflicts_with("values-only")
+                    .help("Only print the id and name of each resource; skips decoding values"),
+            )
+            .arg(
+                Arg::with_name("format")
+                    .long("format")
+                    .takes_value(true)
+                    .possible_values(&["text", "json"])
+                    .default_value("text")
+                    .help("Output format; json requires the json feature"),
+            ),
+        SubCommand::with_name("tree")
+            .about("Print the package/type/entry hierarchy")
+            .args(&apk_args)
+            .arg(
+                Arg::with_name("max-entries")
+                    .long("max-entries")
+                    .takes_value(true)
+                    .default_value("20")
+                    .help("Number of entries to print per type before truncating"),
+            ),
+        SubCommand::with_name("cat")
+            .about("Print the value of a single resource")
+            .args(&apk_args)
+            .arg(
+                Arg::with_name("resource")
+                    .required(true)
+                    .help("Resource id in hex, or name, e.g. string/app_name"),
+            )
+            .arg(
+                Arg::with_name("config")
+                    .long("config")
+                    .takes_value(true)
+                    .help(
+                        "Print the value a device with this configuration would use, e.g. en-rUS",
+                    ),
+            ),
+        SubCommand::with_name("configs")
+            .about("Print the configurations each resource has a value in")
+            .args(&apk_args)
+            .arg(
+                Arg::with_name("type")
+                    .long("type")
+                    .takes_value(true)
+                    .help("Only list resources of this type, e.g. drawable"),
+            ),
+        SubCommand::with_name("lookup-id")
+            .about("Print the name of a resource id")
+            .args(&apk_args)
+            .arg(
+                Arg::with_name("resid")
+                    .required(true)
+                    .help("Resource id in hex, e.g. 0x7f020001"),
+            ),
+        SubCommand::with_name("lookup-name")
+            .about("Print the resource id of a resource name")
+            .args(&apk_args)
+            .arg(Arg::with_name("package").required(true))
+            .arg(Arg::with_name("type").required(true))
+            .arg(Arg::with_name("name").required(true)),
+    ];
+
+    // `arsc <apk>` predates the subcommands and still dumps the table
+    let mut args = env::args_os().collect::<Vec<_>>();
+    if let Some(first) = args.get(1) {
+        let is_subcommand = first == "help" || subcommands.iter().any(|s| first == s.get_name());
+        if !is_subcommand && Path::new(first).is_file() {
+            args.insert(1, "dump".into());
+        }
+    }
     let opts = App::new("arsc")
-        .arg(Arg::with_name("apk").takes_value(true).required(true))
-        .get_matches();
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .after_help("`arsc <apk>` is short for `arsc dump <apk>`.")
+        .subcommands(subcommands)
+        .get_matches_from(args);
 
-    // memory map APK
-    let apk = value_t!(opts.value_of("apk"), String).unwrap();
-    let file = File::open(apk).expect("failed to open file");
-    let mmap = unsafe { MmapOptions::new().map(&file).unwrap() };
+    match opts.subcommand() {
+        ("dump", Some(opts)) => dump(opts),
+        ("tree", Some(opts)) => tree(opts),
+        ("cat", Some(opts)) => cat(opts),
+        ("configs", Some(opts)) => configs(opts),
+        ("lookup-id", Some(opts)) => lookup_id(opts),
+        ("lookup-name", Some(opts)) => lookup_name(opts),
+        _ => unreachable!("clap requires a subcommand"),
+    }
+}
 
-    // read zip header, entry header
+fn map_apk(opts: &ArgMatches) -> Result<Mmap, Error> {
+    let apk = value_t!(opts.value_of("apk"), String).unwrap_or_else(|e| e.exit());
+    let file = File::open(&apk)
+        .map_err(|e| Error::InvalidInput(format!("failed to open {}: {}", apk, e)))?;
+    Ok(unsafe { MmapOptions::new().map(&file)? })
+}
+
+fn find_table<'a>(mmap: &'a Mmap, opts: &ArgMatches) -> Result<Cow<'a, [u8]>, Error> {
+    if opts.is_present("raw") {
+        return Ok(Cow::Borrowed(mmap.as_ref()));
+    }
+
+    // read zip header, entry header; a file that is not a zip but starts like a resource table
+    // is taken to be a loose resources.arsc
     let reader = std::io::Cursor::new(mmap.as_ref());
-    let mut zip = ZipArchive::new(reader).expect("failed to open zip");
-    let entry = zip
-        .by_name("resources.arsc")
-        .expect("failed to unzip resources.arsc");
+    let mut zip = match ZipArchive::new(reader) {
+        Ok(zip) => zip,
+        Err(_) if opts.value_of("entry").is_none() && mmap.starts_with(TABLE_MAGIC) => {
+            return Ok(Cow::Borrowed(mmap.as_ref()))
+        }
+        Err(e) => {
+            return Err(Error::InvalidInput(format!(
+                "failed to open zip: {}; use --raw for a bare resource table",
+                e
+            )))
+        }
+    };
+    let entry_name = match opts.value_of("entry") {
+        Some(name) => name,
+        None => DEFAULT_ENTRY_NAMES
+            .iter()
+            .copied()
+            .find(|name| zip.by_name(name).is_ok())
+            .unwrap_or(DEFAULT_ENTRY_NAMES[0]),
+    };
+    if matches!(zip.by_name(entry_name), Err(ZipError::FileNotFound)) {
+        // list names without opening the entries: compressed ones cannot be opened without the
+        // deflate feature
+        let mut available = zip.file_names().collect::<Vec<_>>();
+        available.sort_unstable();
+        return Err(Error::InvalidInput(format!(
+            "no entry named {} in zip; available entries: {}",
+            entry_name,
+            available.join(", ")
+        )));
+    }
+    let mut entry = zip.by_name(entry_name).map_err(|e| match e {
+        // without the deflate feature, zip refuses to open deflated entries
+        ZipError::UnsupportedArchive(_) if cfg!(not(feature = "deflate")) => Error::Unsupported(
+            format!("compressed {}; enable the `deflate` feature", entry_name),
+        ),
+        e => Error::InvalidInput(format!("failed to unzip {}: {}", entry_name, e)),
+    })?;
     if entry.compression() != CompressionMethod::Stored {
-        panic!("resources.arsc compressed");
+        // compressed entries (common in older or repacked APKs) have to be inflated into memory
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)?;
+        return Ok(Cow::Owned(bytes));
     }
 
     // "extract" the non-compressed entry
     let begin = entry.data_start() as usize;
     let end = begin + entry.size() as usize;
-    let buf = &mmap[begin..end];
+    mmap.get(begin..end).map(Cow::Borrowed).ok_or_else(|| {
+        Error::CorruptData(format!("{} extends past the end of the zip", entry_name))
+    })
+}
 
-    // parse resource table
-    let table = Table::parse(buf).unwrap();
-    for resid in table.resid_iter() {
-        let name = table.name_for_resid(&resid).unwrap();
-        println!("{:?} {:?}", resid, name);
-        for (cfg, v) in table.lookup_all(&resid).unwrap() {
-            println!("    {:?} {:?}", cfg, v);
+fn parse_table<'a>(bytes: &'a [u8], opts: &ArgMatches) -> Result<Table<'a>, Error> {
+    // corrupt data is always an error; data this tool cannot handle yet is skipped unless asked
+    // otherwise
+    let options = ParseOptions {
+        strict: true,
+        skip_unsupported: !opts.is_present("fail-on-unsupported"),
+    };
+    let table = Table::parse_with_options(bytes, options)?;
+    for warning in table.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    Ok(table)
+}
+
+fn dump(opts: &ArgMatches) -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mmap = map_apk(opts)?;
+    let bytes = find_table(&mmap, opts)?;
+    let table = parse_table(&bytes, opts)?;
+    let type_filter = opts.value_of("type");
+    if opts.value_of("format") == Some("json") {
+        return dump_json(&mut out, &table, type_filter);
+    }
+    if opts.is_present("no-values") {
+        for entry in table.entries() {
+            if type_filter.is_none() || type_filter == Some(entry.type_name()) {
+                writeln!(
+                    out,
+                    "{} {}:{}/{}",
+                    entry.id(),
+                    entry.package_name(),
+                    entry.type_name(),
+                    entry.name()
+                )?;
+            }
+        }
+        return Ok(());
+    }
+    let values_only = opts.is_present("values-only");
+    for entry in table.entries() {
+        let resid = entry.id();
+        if matches!(type_filter, Some(type_) if type_ != entry.type_name()) {
+            continue;
+        }
+        let values = match entry.values() {
+            Some(values) => values,
+            None => {
+                eprintln!("warning: failed to decode the values of {:?}", resid);
+                continue;
+            }
+        };
+        if values_only {
+            if let Some((_, v)) = values.iter().find(|(cfg, _)| cfg.is_default()) {
+                writeln!(out, "{}", v)?;
+            }
+            continue;
+        }
+        let name = (entry.package_name(), entry.type_name(), entry.name());
+        writeln!(out, "{:?} {:?}", resid, name)?;
+        for (cfg, v) in values {
+            writeln!(out, "    {} {:?}", cfg, v)?;
         }
     }
-    println!(
-        "resid_for_name={:?}",
-        table.resid_for_name("test.app", "bool", "foo")
-    );
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[derive(serde::Serialize)]
+struct JsonResource {
+    id: String,
+    package: String,
+    #[serde(rename = "type")]
+    type_: String,
+    name: String,
+    value: Option<String>,
+}
+
+// one object per resource, in resource id order, so the output of two APKs can be diffed
+#[cfg(feature = "json")]
+fn dump_json(out: &mut impl Write, table: &Table, type_filter: Option<&str>) -> Result<(), Error> {
+    let mut resources = Vec::new();
+    for entry in table.entries() {
+        if matches!(type_filter, Some(filter) if filter != entry.type_name()) {
+            continue;
+        }
+        let value = entry.value().map(|value| value.to_string());
+        resources.push(JsonResource {
+            id: entry.id().to_string(),
+            package: entry.package_name().to_owned(),
+            type_: entry.type_name().to_owned(),
+            name: entry.name().to_owned(),
+            value,
+        });
+    }
+    let json = serde_json::to_string_pretty(&resources).map_err(|e| Error::IoError(e.into()))?;
+    writeln!(out, "{}", json)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "json"))]
+fn dump_json(
+    _out: &mut impl Write,
+    _table: &Table,
+    _type_filter: Option<&str>,
+) -> Result<(), Error> {
+    Err(Error::Unsupported(
+        "JSON output: rebuild with --features json".to_owned(),
+    ))
+}
+
+fn tree(opts: &ArgMatches) -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let max_entries = value_t!(opts.value_of("max-entries"), usize).unwrap_or_else(|e| e.exit());
+    let mmap = map_apk(opts)?;
+    let bytes = find_table(&mmap, opts)?;
+    let table = parse_table(&bytes, opts)?;
+
+    // mimic tree(1): the last child at each level is drawn with a corner instead of a tee
+    let branch = |last: bool| if last { "└── " } else { "├── " };
+    let indent = |last: bool| if last { "    " } else { "│   " };
+
+    for package in table.package_names() {
+        let types = table.type_names(&package).unwrap_or_default();
+        writeln!(out, "{} ({})", package, types.len())?;
+        for (i, type_) in types.iter().enumerate() {
+            let last_type = i + 1 == types.len();
+            let entries = table.entries_of_type(&package, type_).unwrap_or_default();
+            writeln!(out, "{}{} ({})", branch(last_type), type_, entries.len())?;
+
+            let shown = entries.len().min(max_entries);
+            let truncated = entries.len() - shown;
+            for (j, (_, name)) in entries.iter().take(shown).enumerate() {
+                let last_entry = j + 1 == shown && truncated == 0;
+                writeln!(out, "{}{}{}", indent(last_type), branch(last_entry), name)?;
+            }
+            if truncated > 0 {
+                writeln!(
+                    out,
+                    "{}{}... ({} more)",
+                    indent(last_type),
+                    branch(true),
+                    truncated
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cat(opts: &ArgMatches) -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let config = match opts.value_of("config") {
+        Some(qualifier) => ResourceConfiguration::parse_qualifier(qualifier)?,
+        None => ResourceConfiguration::default(),
+    };
+    let mmap = map_apk(opts)?;
+    let bytes = find_table(&mmap, opts)?;
+    let table = parse_table(&bytes, opts)?;
+    let resid = table.parse_resid(opts.value_of("resource").unwrap())?;
+    if table.name_for_resid(&resid).is_none() {
+        return Err(Error::InvalidInput(format!(
+            "no resource with id {}",
+            resid
+        )));
+    }
+    if table.value_for_resid(&resid).is_none() {
+        return Err(Error::CorruptData(format!(
+            "failed to decode the values of {}",
+            resid
+        )));
+    }
+    // pick the value the way a device with this configuration would
+    let value = table
+        .value_for_resid_config(&resid, &config)
+        .ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "no value for {} matching configuration {}",
+                resid, config
+            ))
+        })?;
+    writeln!(out, "{}", value)?;
+    Ok(())
+}
+
+// sorted by resource id, so the output of two builds can be diffed
+fn configs(opts: &ArgMatches) -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mmap = map_apk(opts)?;
+    let bytes = find_table(&mmap, opts)?;
+    let table = parse_table(&bytes, opts)?;
+    let type_filter = opts.value_of("type");
+    let mut entries = table
+        .entries()
+        .filter(|entry| type_filter.is_none() || type_filter == Some(entry.type_name()))
+        .collect::<Vec<_>>();
+    entries.sort_unstable_by_key(|entry| entry.id());
+    for entry in entries {
+        let configs = entry
+            .configurations()
+            .iter()
+            .map(|config| config.to_string())
+            .collect::<Vec<_>>();
+        writeln!(
+            out,
+            "{} {}:{}/{}: {}",
+            entry.id(),
+            entry.package_name(),
+            entry.type_name(),
+            entry.name(),
+            configs.join(", ")
+        )?;
+    }
+    Ok(())
+}
+
+fn lookup_id(opts: &ArgMatches) -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let resid: ResourceId = opts.value_of("resid").unwrap().parse()?;
+    let mmap = map_apk(opts)?;
+    let bytes = find_table(&mmap, opts)?;
+    let table = parse_table(&bytes, opts)?;
+    let (package, type_, name) = table
+        .name_for_resid(&resid)
+        .ok_or_else(|| Error::InvalidInput(format!("no resource with id {}", resid)))?;
+    writeln!(out, "{}:{}/{}", package, type_, name)?;
+    Ok(())
+}
+
+fn lookup_name(opts: &ArgMatches) -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let package = opts.value_of("package").unwrap();
+    let type_ = opts.value_of("type").unwrap();
+    let name = opts.value_of("name").unwrap();
+    let mmap = map_apk(opts)?;
+    let bytes = find_table(&mmap, opts)?;
+    let table = parse_table(&bytes, opts)?;
+    let resid = table.resid_for_name(package, type_, name).ok_or_else(|| {
+        Error::InvalidInput(format!("no resource named {}:{}/{}", package, type_, name))
+    })?;
+    writeln!(out, "{}", resid)?;
+    Ok(())
 }
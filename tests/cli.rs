@@ -0,0 +1,71 @@
+use std::process::{Command, Output, Stdio};
+
+fn arsc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_arsc"))
+        .args(args)
+        .output()
+        .expect("failed to run arsc")
+}
+
+#[test]
+fn stored_table() {
+    let output = arsc(&["lookup-id", "tests/data/test-app.apk", "0x7f010000"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "test.app:bool/foo\n"
+    );
+}
+
+// test-app-deflated.apk is test-app.apk with resources.arsc deflated instead of stored
+#[test]
+fn compressed_table() {
+    let output = arsc(&[
+        "lookup-id",
+        "tests/data/test-app-deflated.apk",
+        "0x7f010000",
+    ]);
+    if cfg!(feature = "deflate") {
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "test.app:bool/foo\n"
+        );
+    } else {
+        assert!(!output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "error: unsupported: compressed resources.arsc; enable the `deflate` feature\n"
+        );
+    }
+}
+
+#[test]
+fn configs() {
+    let output = arsc(&["configs", "tests/data/test-app.apk"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "0x7f010000 test.app:bool/foo: default\n\
+         0x7f020000 test.app:string/app_name: default, en-rXA, ar-rXB\n\
+         0x7f020001 test.app:string/foo: default, sv, en-rXA, ar-rXB\n"
+    );
+}
+
+// a reader that goes away, as in `arsc dump app.apk | head`, ends the output quietly
+#[cfg(unix)]
+#[test]
+fn closed_stdout() {
+    // once `true` has exited, nothing reads from the pipe behind its stdin
+    let mut reader = Command::new("true").stdin(Stdio::piped()).spawn().unwrap();
+    let pipe = reader.stdin.take().unwrap();
+    reader.wait().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_arsc"))
+        .arg("tests/data/unpacked/resources.arsc")
+        .stdout(pipe)
+        .output()
+        .expect("failed to run arsc");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}